@@ -1,4 +1,6 @@
 use super::expr::{Expr, Visitor};
+use super::stmt;
+use super::stmt::{Case, Function, Signature, Stmt};
 use super::token::Token;
 
 pub struct ASTPrinter;
@@ -8,6 +10,37 @@ impl ASTPrinter {
         let mut printer = ASTPrinter {};
         expr.accept(&mut printer)
     }
+
+    pub fn print_stmt(stmt: &Stmt) -> String {
+        let mut printer = ASTPrinter {};
+        stmt.accept(&mut printer)
+    }
+
+    // Whole program, one top-level statement per line
+    pub fn print_program(stmts: &[Stmt]) -> String {
+        let mut printer = ASTPrinter {};
+        stmts
+            .iter()
+            .map(|stmt| format!("{}\n", stmt.accept(&mut printer)))
+            .collect()
+    }
+
+    fn statements(&mut self, name: &str, statements: &[Stmt]) -> String {
+        let mut builder = format!("({}", name);
+
+        for stmt in statements {
+            let inner = format!(" {}", stmt.accept(self));
+            builder.push_str(&inner);
+        }
+        builder.push(')');
+
+        builder
+    }
+
+    fn signature(name: &Token, params: &[Token]) -> String {
+        let params: Vec<&str> = params.iter().map(|param| param.lexeme.as_str()).collect();
+        format!("{}({})", name.lexeme, params.join(", "))
+    }
     fn parenthesize(&mut self, name: &str, exprs: &[&Expr]) -> String {
         let mut builder = format!("({}", name);
 
@@ -15,7 +48,7 @@ impl ASTPrinter {
             let inner = format!(" {}", expr.accept(self));
             builder.push_str(&inner);
         }
-        builder.push_str(")");
+        builder.push(')');
 
         builder
     }
@@ -57,41 +90,540 @@ impl Visitor<String> for ASTPrinter {
         self.parenthesize("Cond", &[cond, then_branch, else_branch])
     }
 
-    fn visit_variable_expr(&mut self, _token: &Token, _: u64) -> String {
-        todo!()
+    fn visit_variable_expr(&mut self, token: &Token, _: u64) -> String {
+        token.lexeme.clone()
     }
 
-    fn visit_assign_expr(&mut self, _token: &Token, _expr: &Expr, _: u64) -> String {
-        todo!()
+    fn visit_assign_expr(&mut self, token: &Token, expr: &Expr, _: u64) -> String {
+        self.parenthesize(&format!("= {}", token.lexeme), &[expr])
     }
 
-    fn visit_logic_or(&mut self, _left: &Expr, _right: &Expr) -> String {
-        todo!()
+    fn visit_logic_or(&mut self, left: &Expr, right: &Expr) -> String {
+        self.parenthesize("or", &[left, right])
     }
 
-    fn visit_logic_and(&mut self, _left: &Expr, _right: &Expr) -> String {
-        todo!()
+    fn visit_logic_xor(&mut self, left: &Expr, right: &Expr) -> String {
+        self.parenthesize("xor", &[left, right])
     }
 
-    fn visit_call_expr(&mut self, _callee: &Expr, _token: &Token, _args: &[Expr]) -> String {
-        todo!()
+    fn visit_logic_and(&mut self, left: &Expr, right: &Expr) -> String {
+        self.parenthesize("and", &[left, right])
     }
 
-    fn visit_get_expr(&mut self, _object: &Expr, _property: &Token) -> String {
-        todo!()
+    fn visit_call_expr(&mut self, callee: &Expr, _token: &Token, args: &[Expr]) -> String {
+        let exprs: Vec<&Expr> = std::iter::once(callee).chain(args).collect();
+        self.parenthesize("call", &exprs)
     }
 
-    fn visit_set_expr(&mut self, _object: &Expr, _property: &Token, _value: &Expr) -> String {
-        todo!()
+    fn visit_get_expr(&mut self, object: &Expr, property: &Token) -> String {
+        let object = object.accept(self);
+        format!("(. {} {})", object, property.lexeme)
     }
 
-    fn visit_this_expr(&mut self, token: &Token, _id: u64) -> String {
-        todo!()
+    fn visit_set_expr(&mut self, object: &Expr, property: &Token, value: &Expr) -> String {
+        let object = object.accept(self);
+        let value = value.accept(self);
+        format!("(= (. {} {}) {})", object, property.lexeme, value)
+    }
+
+    fn visit_compound_set_expr(
+        &mut self,
+        object: &Expr,
+        property: &Token,
+        operator: &Token,
+        value: &Expr,
+    ) -> String {
+        let object = object.accept(self);
+        let value = value.accept(self);
+        format!(
+            "({}= (. {} {}) {})",
+            operator.lexeme, object, property.lexeme, value
+        )
+    }
+
+    fn visit_this_expr(&mut self, _token: &Token, _id: u64) -> String {
+        "this".into()
+    }
+
+    fn visit_super_expr(&mut self, _keyword: &Token, method: &Token, _id: u64) -> String {
+        format!("(super {})", method.lexeme)
+    }
+
+    fn visit_lambda_expr(&mut self, params: &[Token], body: &[Stmt]) -> String {
+        let params: Vec<&str> = params.iter().map(|param| param.lexeme.as_str()).collect();
+        self.statements(&format!("fun ({})", params.join(", ")), body)
+    }
+
+    fn visit_map_expr(&mut self, _brace: &Token, entries: &[(Expr, Expr)]) -> String {
+        let exprs: Vec<&Expr> = entries
+            .iter()
+            .flat_map(|(key, value)| vec![key, value])
+            .collect();
+        self.parenthesize("map", &exprs)
+    }
+
+    fn visit_index_expr(&mut self, object: &Expr, _bracket: &Token, index: &Expr) -> String {
+        self.parenthesize("[]", &[object, index])
+    }
+
+    fn visit_index_set_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> String {
+        self.parenthesize("[]=", &[object, index, value])
+    }
+}
+
+impl stmt::Visitor<String> for ASTPrinter {
+    fn visit_block_stmt(&mut self, statements: &[Stmt]) -> String {
+        self.statements("block", statements)
+    }
+
+    fn visit_expression_stmt(&mut self, expr: &Expr) -> String {
+        self.parenthesize(";", &[expr])
+    }
+
+    fn visit_print_stmt(&mut self, values: &[Expr]) -> String {
+        let values: Vec<&Expr> = values.iter().collect();
+        self.parenthesize("print", &values)
+    }
+
+    fn visit_var_stmt(&mut self, token: &Token, _: Option<&Token>, expr: Option<&Expr>) -> String {
+        match expr {
+            Some(expr) => self.parenthesize(&format!("var {} =", token.lexeme), &[expr]),
+            None => format!("(var {})", token.lexeme),
+        }
+    }
+
+    fn visit_if_stmt(
+        &mut self,
+        cond: &Expr,
+        then_branch: &Stmt,
+        else_branch: Option<&Stmt>,
+    ) -> String {
+        let cond = cond.accept(self);
+        let then_branch = then_branch.accept(self);
+        match else_branch {
+            Some(else_branch) => format!(
+                "(if-else {} {} {})",
+                cond,
+                then_branch,
+                else_branch.accept(self)
+            ),
+            None => format!("(if {} {})", cond, then_branch),
+        }
+    }
+
+    fn visit_while_stmt(
+        &mut self,
+        cond: &Expr,
+        block: &Stmt,
+        increment: Option<&Expr>,
+        else_branch: Option<&Stmt>,
+    ) -> String {
+        let mut builder = format!("(while {} {}", cond.accept(self), block.accept(self));
+        if let Some(increment) = increment {
+            builder.push_str(&format!(" (step {})", increment.accept(self)));
+        }
+        if let Some(else_branch) = else_branch {
+            builder.push_str(&format!(" (else {})", else_branch.accept(self)));
+        }
+        builder.push(')');
+
+        builder
+    }
+
+    fn visit_function_stmt(
+        &mut self,
+        name: &Token,
+        params: &[Token],
+        body: &[Stmt],
+        _: &Signature,
+    ) -> String {
+        let signature = format!("fun {}", ASTPrinter::signature(name, params));
+        self.statements(&signature, body)
+    }
+
+    fn visit_switch_stmt(
+        &mut self,
+        value: &Expr,
+        cases: &[Case],
+        default: Option<&[Stmt]>,
+    ) -> String {
+        let mut builder = format!("(switch {}", value.accept(self));
+        for (case, body) in cases {
+            let name = format!("case {}", case.accept(self));
+            builder.push_str(&format!(" {}", self.statements(&name, body)));
+        }
+        if let Some(default) = default {
+            builder.push_str(&format!(" {}", self.statements("default", default)));
+        }
+        builder.push(')');
+
+        builder
+    }
+
+    fn visit_return_stmt(&mut self, _token: &Token, expr: Option<&Expr>) -> String {
+        let exprs: Vec<&Expr> = expr.into_iter().collect();
+        self.parenthesize("return", &exprs)
+    }
+
+    fn visit_break_stmt(&mut self, _token: &Token) -> String {
+        "(break)".into()
+    }
+
+    fn visit_continue_stmt(&mut self, _token: &Token) -> String {
+        "(continue)".into()
+    }
+
+    fn visit_class_stmt(
+        &mut self,
+        token: &Token,
+        superclass: Option<&Expr>,
+        methods: &[Function],
+    ) -> String {
+        let mut builder = format!("(class {}", token.lexeme);
+        if let Some(Expr::Variable(superclass, _)) = superclass {
+            builder.push_str(&format!(" < {}", superclass.lexeme));
+        }
+        for (name, params, ..) in methods {
+            builder.push_str(&format!(
+                " (method {})",
+                ASTPrinter::signature(name, params)
+            ));
+        }
+        builder.push(')');
+
+        builder
+    }
+}
+
+// --- GraphViz DOT ---
+// Every node is named `n<id>` and every edge is labeled with the role of the child
+#[derive(Default)]
+pub struct DotPrinter {
+    next_id: usize,
+    lines: Vec<String>,
+}
+
+impl DotPrinter {
+    pub fn print(stmts: &[Stmt]) -> String {
+        let mut printer = DotPrinter::default();
+        let root = printer.node("Program");
+        printer.stmt_children(&root, "stmt", stmts);
+
+        let mut builder = String::from("digraph {\n");
+        for line in printer.lines {
+            builder.push_str(&format!("  {}\n", line));
+        }
+        builder.push('}');
+
+        builder
+    }
+
+    fn node(&mut self, label: &str) -> String {
+        let id = format!("n{}", self.next_id);
+        self.next_id += 1;
+        let label = label.replace('\\', "\\\\").replace('"', "\\\"");
+        self.lines
+            .push(format!("{} [shape=box, label=\"{}\"];", id, label));
+        id
+    }
+
+    fn edge(&mut self, from: &str, to: &str, label: &str) {
+        self.lines
+            .push(format!("{} -> {} [label=\"{}\"];", from, to, label));
+    }
+
+    fn expr_child(&mut self, parent: &str, label: &str, expr: &Expr) {
+        let child = expr.accept(self);
+        self.edge(parent, &child, label);
+    }
+
+    fn stmt_child(&mut self, parent: &str, label: &str, stmt: &Stmt) {
+        let child = stmt.accept(self);
+        self.edge(parent, &child, label);
+    }
+
+    fn stmt_children(&mut self, parent: &str, label: &str, stmts: &[Stmt]) {
+        for stmt in stmts {
+            self.stmt_child(parent, label, stmt);
+        }
+    }
+
+    fn expr_node(&mut self, label: &str, children: &[(&str, &Expr)]) -> String {
+        let id = self.node(label);
+        for (edge, child) in children {
+            self.expr_child(&id, edge, child);
+        }
+        id
+    }
+}
+
+impl Visitor<String> for DotPrinter {
+    fn visit_binary_expr(&mut self, left: &Expr, token: &Token, right: &Expr) -> String {
+        let label = format!("Binary {}", token.lexeme);
+        self.expr_node(&label, &[("left", left), ("right", right)])
+    }
+
+    fn visit_grouping_expr(&mut self, expr: &Expr) -> String {
+        self.expr_node("Grouping", &[("expr", expr)])
+    }
+
+    fn visit_unary_expr(&mut self, token: &Token, expr: &Expr) -> String {
+        self.expr_node(&format!("Unary {}", token.lexeme), &[("expr", expr)])
+    }
+
+    fn visit_call_expr(&mut self, callee: &Expr, _token: &Token, args: &[Expr]) -> String {
+        let id = self.expr_node("Call", &[("callee", callee)]);
+        for arg in args {
+            self.expr_child(&id, "arg", arg);
+        }
+        id
+    }
+
+    fn visit_conditional_expr(
+        &mut self,
+        cond: &Expr,
+        then_branch: &Expr,
+        else_branch: &Expr,
+    ) -> String {
+        self.expr_node(
+            "Conditional",
+            &[("cond", cond), ("then", then_branch), ("else", else_branch)],
+        )
+    }
+
+    fn visit_literal_expr_number(&mut self, value: f64) -> String {
+        self.node(&format!("Number {}", value))
+    }
+
+    fn visit_literal_expr_string(&mut self, value: &str) -> String {
+        self.node(&format!("String {}", value))
+    }
+
+    fn visit_literal_expr_boolean(&mut self, value: bool) -> String {
+        self.node(&format!("Boolean {}", value))
+    }
+
+    fn visit_literal_expr_nil(&mut self) -> String {
+        self.node("Nil")
+    }
+
+    fn visit_variable_expr(&mut self, token: &Token, _: u64) -> String {
+        self.node(&format!("Variable {}", token.lexeme))
+    }
+
+    fn visit_assign_expr(&mut self, token: &Token, expr: &Expr, _: u64) -> String {
+        let label = format!("Assign {}", token.lexeme);
+        self.expr_node(&label, &[("value", expr)])
+    }
+
+    fn visit_logic_or(&mut self, left: &Expr, right: &Expr) -> String {
+        self.expr_node("Or", &[("left", left), ("right", right)])
+    }
+
+    fn visit_logic_xor(&mut self, left: &Expr, right: &Expr) -> String {
+        self.expr_node("Xor", &[("left", left), ("right", right)])
+    }
+
+    fn visit_logic_and(&mut self, left: &Expr, right: &Expr) -> String {
+        self.expr_node("And", &[("left", left), ("right", right)])
+    }
+
+    fn visit_get_expr(&mut self, object: &Expr, property: &Token) -> String {
+        let label = format!("Get {}", property.lexeme);
+        self.expr_node(&label, &[("object", object)])
+    }
+
+    fn visit_set_expr(&mut self, object: &Expr, property: &Token, value: &Expr) -> String {
+        let label = format!("Set {}", property.lexeme);
+        self.expr_node(&label, &[("object", object), ("value", value)])
+    }
+
+    fn visit_compound_set_expr(
+        &mut self,
+        object: &Expr,
+        property: &Token,
+        operator: &Token,
+        value: &Expr,
+    ) -> String {
+        let label = format!("CompoundSet {} {}=", property.lexeme, operator.lexeme);
+        self.expr_node(&label, &[("object", object), ("value", value)])
+    }
+
+    fn visit_this_expr(&mut self, _token: &Token, _id: u64) -> String {
+        self.node("This")
+    }
+
+    fn visit_super_expr(&mut self, _keyword: &Token, method: &Token, _id: u64) -> String {
+        self.node(&format!("Super {}", method.lexeme))
+    }
+
+    fn visit_lambda_expr(&mut self, params: &[Token], body: &[Stmt]) -> String {
+        let params: Vec<&str> = params.iter().map(|param| param.lexeme.as_str()).collect();
+        let id = self.node(&format!("Lambda ({})", params.join(", ")));
+        self.stmt_children(&id, "body", body);
+        id
+    }
+
+    fn visit_map_expr(&mut self, _brace: &Token, entries: &[(Expr, Expr)]) -> String {
+        let id = self.node("Map");
+        for (key, value) in entries {
+            self.expr_child(&id, "key", key);
+            self.expr_child(&id, "value", value);
+        }
+        id
+    }
+
+    fn visit_index_expr(&mut self, object: &Expr, _bracket: &Token, index: &Expr) -> String {
+        self.expr_node("Index", &[("object", object), ("index", index)])
+    }
+
+    fn visit_index_set_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> String {
+        self.expr_node(
+            "IndexSet",
+            &[("object", object), ("index", index), ("value", value)],
+        )
+    }
+}
+
+impl stmt::Visitor<String> for DotPrinter {
+    fn visit_block_stmt(&mut self, statements: &[Stmt]) -> String {
+        let id = self.node("Block");
+        self.stmt_children(&id, "stmt", statements);
+        id
+    }
+
+    fn visit_expression_stmt(&mut self, expr: &Expr) -> String {
+        self.expr_node("Expression", &[("expr", expr)])
+    }
+
+    fn visit_print_stmt(&mut self, values: &[Expr]) -> String {
+        let id = self.node("Print");
+        for value in values {
+            self.expr_child(&id, "expr", value);
+        }
+        id
+    }
+
+    fn visit_var_stmt(&mut self, token: &Token, _: Option<&Token>, expr: Option<&Expr>) -> String {
+        let id = self.node(&format!("Var {}", token.lexeme));
+        if let Some(expr) = expr {
+            self.expr_child(&id, "init", expr);
+        }
+        id
+    }
+
+    fn visit_if_stmt(
+        &mut self,
+        cond: &Expr,
+        then_branch: &Stmt,
+        else_branch: Option<&Stmt>,
+    ) -> String {
+        let id = self.expr_node("If", &[("cond", cond)]);
+        self.stmt_child(&id, "then", then_branch);
+        if let Some(else_branch) = else_branch {
+            self.stmt_child(&id, "else", else_branch);
+        }
+        id
+    }
+
+    fn visit_while_stmt(
+        &mut self,
+        cond: &Expr,
+        block: &Stmt,
+        increment: Option<&Expr>,
+        else_branch: Option<&Stmt>,
+    ) -> String {
+        let id = self.expr_node("While", &[("cond", cond)]);
+        self.stmt_child(&id, "body", block);
+        if let Some(increment) = increment {
+            self.expr_child(&id, "step", increment);
+        }
+        if let Some(else_branch) = else_branch {
+            self.stmt_child(&id, "else", else_branch);
+        }
+        id
+    }
+
+    fn visit_function_stmt(
+        &mut self,
+        name: &Token,
+        params: &[Token],
+        body: &[Stmt],
+        _: &Signature,
+    ) -> String {
+        let id = self.node(&format!("Function {}", ASTPrinter::signature(name, params)));
+        self.stmt_children(&id, "body", body);
+        id
+    }
+
+    fn visit_switch_stmt(
+        &mut self,
+        value: &Expr,
+        cases: &[Case],
+        default: Option<&[Stmt]>,
+    ) -> String {
+        let id = self.expr_node("Switch", &[("value", value)]);
+        for (case, body) in cases {
+            let case_id = self.expr_node("Case", &[("value", case)]);
+            self.stmt_children(&case_id, "stmt", body);
+            self.edge(&id, &case_id, "case");
+        }
+        if let Some(default) = default {
+            let default_id = self.node("Default");
+            self.stmt_children(&default_id, "stmt", default);
+            self.edge(&id, &default_id, "default");
+        }
+        id
+    }
+
+    fn visit_return_stmt(&mut self, _token: &Token, expr: Option<&Expr>) -> String {
+        let children: Vec<(&str, &Expr)> = expr.map(|expr| ("value", expr)).into_iter().collect();
+        self.expr_node("Return", &children)
+    }
+
+    fn visit_break_stmt(&mut self, _token: &Token) -> String {
+        self.node("Break")
+    }
+
+    fn visit_continue_stmt(&mut self, _token: &Token) -> String {
+        self.node("Continue")
+    }
+
+    fn visit_class_stmt(
+        &mut self,
+        token: &Token,
+        superclass: Option<&Expr>,
+        methods: &[Function],
+    ) -> String {
+        let id = self.node(&format!("Class {}", token.lexeme));
+        if let Some(superclass) = superclass {
+            self.expr_child(&id, "superclass", superclass);
+        }
+        for (name, params, body, _) in methods {
+            let method = self.node(&format!("Method {}", ASTPrinter::signature(name, params)));
+            self.stmt_children(&method, "body", body);
+            self.edge(&id, &method, "method");
+        }
+        id
     }
 }
 
 // --- Reverse Polish Notation ---
+#[allow(dead_code)]
 struct RPNPrinter {}
+#[allow(dead_code)]
 impl RPNPrinter {
     fn print(&mut self, expr: &Expr) -> String {
         expr.accept(self)
@@ -156,6 +688,10 @@ impl Visitor<String> for RPNPrinter {
         todo!()
     }
 
+    fn visit_logic_xor(&mut self, left: &Expr, right: &Expr) -> String {
+        self.parenthesize("xor", &[left, right])
+    }
+
     fn visit_logic_and(&mut self, _left: &Expr, _right: &Expr) -> String {
         todo!()
     }
@@ -172,9 +708,56 @@ impl Visitor<String> for RPNPrinter {
         todo!()
     }
 
-    fn visit_this_expr(&mut self, token: &Token, _id: u64) -> String {
+    fn visit_compound_set_expr(
+        &mut self,
+        object: &Expr,
+        property: &Token,
+        operator: &Token,
+        value: &Expr,
+    ) -> String {
+        let object = object.accept(self);
+        let value = value.accept(self);
+        format!(
+            "{} {} .{} {}=",
+            object, value, property.lexeme, operator.lexeme
+        )
+    }
+
+    fn visit_this_expr(&mut self, _token: &Token, _id: u64) -> String {
         todo!()
     }
+
+    fn visit_super_expr(&mut self, _keyword: &Token, method: &Token, _id: u64) -> String {
+        format!("super.{}", method.lexeme)
+    }
+
+    // only expressions have a postfix form, so the body is left out
+    fn visit_lambda_expr(&mut self, params: &[Token], _body: &[Stmt]) -> String {
+        let params: Vec<&str> = params.iter().map(|param| param.lexeme.as_str()).collect();
+        format!("fun({})", params.join(", "))
+    }
+
+    fn visit_map_expr(&mut self, _brace: &Token, entries: &[(Expr, Expr)]) -> String {
+        let exprs: Vec<&Expr> = entries
+            .iter()
+            .flat_map(|(key, value)| vec![key, value])
+            .collect();
+        self.parenthesize("map", &exprs)
+    }
+
+    fn visit_index_expr(&mut self, object: &Expr, _bracket: &Token, index: &Expr) -> String {
+        self.parenthesize("[]", &[object, index])
+    }
+
+    fn visit_index_set_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> String {
+        self.parenthesize("[]=", &[object, index, value])
+    }
 }
 
 #[cfg(test)]
@@ -217,4 +800,147 @@ mod test {
         let output = RPNPrinter {}.print(&mul);
         assert_eq!(output, "1 2 + 4 3 - *");
     }
+
+    fn parse(source: &str) -> Vec<Stmt> {
+        crate::lox::run(source.to_string())
+    }
+
+    fn rpn(source: &str) -> String {
+        match &parse(source)[0] {
+            Stmt::Expression(expr) => RPNPrinter {}.print(expr),
+            x => panic!("expected expression statement, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn test_logic_xor_rpn_printer() {
+        assert_eq!(rpn("true xor 1 < 2;"), "true 1 2 < xor");
+    }
+
+    #[test]
+    fn test_lambda_rpn_printer() {
+        assert_eq!(rpn("(fun (a, b) { print a + b; });"), "fun(a, b) Group");
+    }
+
+    #[test]
+    fn test_super_rpn_printer() {
+        assert_eq!(rpn("super.method;"), "super.method");
+    }
+
+    #[test]
+    fn test_map_rpn_printer() {
+        assert_eq!(rpn("({\"a\": 1})[\"a\"];"), "a 1 map Group a []");
+        assert_eq!(rpn("({\"a\": 1})[\"a\"] = 2;"), "a 1 map Group a 2 []=");
+    }
+
+    #[test]
+    fn test_class_stmt_printer() {
+        let stmts = parse("class Foo < Bar { baz(a, b) { print a; } }");
+
+        let output = ASTPrinter::print_stmt(&stmts[0]);
+        assert_eq!(output, "(class Foo < Bar (method baz(a, b)))");
+    }
+
+    #[test]
+    fn test_if_stmt_printer() {
+        let stmts = parse("if (1 < 2) print 1; else { print 2; }");
+
+        let output = ASTPrinter::print_stmt(&stmts[0]);
+        assert_eq!(output, "(if-else (< 1 2) (print 1) (block (print 2)))");
+    }
+
+    #[test]
+    fn test_assign_expr_printer() {
+        let stmts = parse("var x; x = 5;");
+
+        let output = ASTPrinter::print_stmt(&stmts[1]);
+        assert_eq!(output, "(; (= x 5))");
+    }
+
+    #[test]
+    fn test_logic_or_expr_printer() {
+        let stmts = parse("var a; var b; print a or b and true;");
+
+        let output = ASTPrinter::print_stmt(&stmts[2]);
+        assert_eq!(output, "(print (or a (and b true)))");
+    }
+
+    #[test]
+    fn test_logic_xor_expr_printer() {
+        let stmts = parse("var a; var b; print a or b xor a and true;");
+
+        let output = ASTPrinter::print_stmt(&stmts[2]);
+        assert_eq!(output, "(print (or a (xor b (and a true))))");
+    }
+
+    #[test]
+    fn test_call_expr_printer() {
+        let stmts = parse("var f; var a; f(a, 1 + 2);");
+
+        let output = ASTPrinter::print_stmt(&stmts[2]);
+        assert_eq!(output, "(; (call f a (+ 1 2)))");
+    }
+
+    #[test]
+    fn test_property_expr_printer() {
+        let stmts = parse("var p; p.x = p.y;");
+
+        let output = ASTPrinter::print_stmt(&stmts[1]);
+        assert_eq!(output, "(; (= (. p x) (. p y)))");
+    }
+
+    #[test]
+    fn test_var_stmt_printer() {
+        let stmts = parse("var x = 5; var y;");
+
+        assert_eq!(ASTPrinter::print_stmt(&stmts[0]), "(var x = 5)");
+        assert_eq!(ASTPrinter::print_stmt(&stmts[1]), "(var y)");
+    }
+
+    #[test]
+    fn test_block_stmt_printer() {
+        let stmts = parse("{ write(1); print 2; }");
+
+        let output = ASTPrinter::print_stmt(&stmts[0]);
+        assert_eq!(output, "(block (; (call write 1)) (print 2))");
+    }
+
+    #[test]
+    fn test_while_stmt_printer() {
+        let stmts = parse("while (true) { break; } for (var i = 0; i < 2; i = i + 1) continue;");
+
+        assert_eq!(
+            ASTPrinter::print_stmt(&stmts[0]),
+            "(while true (block (break)))"
+        );
+        assert_eq!(
+            ASTPrinter::print_stmt(&stmts[1]),
+            "(block (var i = 0) (while (< i 2) (continue) (step (= i (+ i 1)))))"
+        );
+    }
+
+    #[test]
+    fn test_function_stmt_printer() {
+        let stmts = parse("fun add(a, b) { return a + b; }");
+
+        let output = ASTPrinter::print_stmt(&stmts[0]);
+        assert_eq!(output, "(fun add(a, b) (return (+ a b)))");
+    }
+
+    #[test]
+    fn test_dot_printer() {
+        let stmts = parse("var s = \"a\\\"b\"; if (s) print -1; else print s + \"!\";");
+
+        let output = DotPrinter::print(&stmts);
+        assert!(output.starts_with("digraph {\n"));
+        assert!(output.ends_with('}'));
+        assert_eq!(output.matches('{').count(), output.matches('}').count());
+        assert!(output.contains("[shape=box, label=\"String a\\\"b\"];"));
+
+        // Program, Var, String, If, Variable, Print, Unary, Number, Print, Binary, Variable, String
+        let nodes = output.matches("[shape=box").count();
+        let edges = output.matches(" -> ").count();
+        assert_eq!(nodes, 12);
+        assert_eq!(edges, nodes - 1);
+    }
 }
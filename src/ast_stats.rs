@@ -0,0 +1,360 @@
+use super::expr;
+use super::expr::Expr;
+use super::stmt;
+use super::stmt::{Case, Function, Signature, Stmt};
+use super::token::Token;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+// Counts how many nodes of each kind a program has and how deep its tree goes
+#[derive(Default, Debug)]
+pub struct AstStats {
+    counts: BTreeMap<&'static str, usize>,
+    total: usize,
+    max_depth: usize,
+    depth: usize,
+}
+
+impl AstStats {
+    pub fn new(statements: &[Stmt]) -> Self {
+        let mut stats = AstStats::default();
+        statements.iter().for_each(|stmt| stmt.accept(&mut stats));
+        stats
+    }
+
+    pub fn count(&self, kind: &str) -> usize {
+        self.counts.get(kind).cloned().unwrap_or(0)
+    }
+
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    fn enter(&mut self, kind: &'static str) {
+        *self.counts.entry(kind).or_insert(0) += 1;
+        self.total += 1;
+        self.depth += 1;
+        self.max_depth = self.max_depth.max(self.depth);
+    }
+
+    fn exit(&mut self) {
+        self.depth -= 1;
+    }
+
+    fn leaf(&mut self, kind: &'static str) {
+        self.enter(kind);
+        self.exit();
+    }
+
+    fn function(&mut self, body: &[Stmt]) {
+        self.enter("Stmt::Function");
+        body.iter().for_each(|stmt| stmt.accept(self));
+        self.exit();
+    }
+}
+
+impl Display for AstStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (kind, count) in &self.counts {
+            writeln!(f, "{} {}", kind, count)?;
+        }
+        writeln!(f, "Total nodes: {}", self.total)?;
+        write!(f, "Max depth: {}", self.max_depth)
+    }
+}
+
+impl stmt::Visitor<()> for AstStats {
+    fn visit_block_stmt(&mut self, statements: &[Stmt]) {
+        self.enter("Stmt::Block");
+        statements.iter().for_each(|stmt| stmt.accept(self));
+        self.exit();
+    }
+
+    fn visit_expression_stmt(&mut self, expr: &Expr) {
+        self.enter("Stmt::Expression");
+        expr.accept(self);
+        self.exit();
+    }
+
+    fn visit_print_stmt(&mut self, values: &[Expr]) {
+        self.enter("Stmt::Print");
+        values.iter().for_each(|value| value.accept(self));
+        self.exit();
+    }
+
+    fn visit_var_stmt(&mut self, _token: &Token, _: Option<&Token>, expr: Option<&Expr>) {
+        self.enter("Stmt::Var");
+        if let Some(expr) = expr {
+            expr.accept(self);
+        }
+        self.exit();
+    }
+
+    fn visit_if_stmt(&mut self, cond: &Expr, then_branch: &Stmt, else_branch: Option<&Stmt>) {
+        self.enter("Stmt::If");
+        cond.accept(self);
+        then_branch.accept(self);
+        if let Some(else_branch) = else_branch {
+            else_branch.accept(self);
+        }
+        self.exit();
+    }
+
+    fn visit_while_stmt(
+        &mut self,
+        cond: &Expr,
+        block: &Stmt,
+        increment: Option<&Expr>,
+        else_branch: Option<&Stmt>,
+    ) {
+        self.enter("Stmt::While");
+        cond.accept(self);
+        block.accept(self);
+        if let Some(increment) = increment {
+            increment.accept(self);
+        }
+        if let Some(else_branch) = else_branch {
+            else_branch.accept(self);
+        }
+        self.exit();
+    }
+
+    fn visit_function_stmt(&mut self, _: &Token, _: &[Token], body: &[Stmt], _: &Signature) {
+        self.function(body);
+    }
+
+    fn visit_switch_stmt(&mut self, value: &Expr, cases: &[Case], default: Option<&[Stmt]>) {
+        self.enter("Stmt::Switch");
+        value.accept(self);
+        for (case, body) in cases {
+            case.accept(self);
+            body.iter().for_each(|stmt| stmt.accept(self));
+        }
+        default
+            .unwrap_or_default()
+            .iter()
+            .for_each(|stmt| stmt.accept(self));
+        self.exit();
+    }
+
+    fn visit_return_stmt(&mut self, _token: &Token, expr: Option<&Expr>) {
+        self.enter("Stmt::Return");
+        if let Some(expr) = expr {
+            expr.accept(self);
+        }
+        self.exit();
+    }
+
+    fn visit_break_stmt(&mut self, _token: &Token) {
+        self.leaf("Stmt::Break");
+    }
+
+    fn visit_continue_stmt(&mut self, _token: &Token) {
+        self.leaf("Stmt::Continue");
+    }
+
+    fn visit_class_stmt(
+        &mut self,
+        _token: &Token,
+        superclass: Option<&Expr>,
+        methods: &[Function],
+    ) {
+        self.enter("Stmt::Class");
+        if let Some(superclass) = superclass {
+            superclass.accept(self);
+        }
+        methods
+            .iter()
+            .for_each(|(_, _, body, _)| self.function(body));
+        self.exit();
+    }
+}
+
+impl expr::Visitor<()> for AstStats {
+    fn visit_binary_expr(&mut self, left: &Expr, _token: &Token, right: &Expr) {
+        self.enter("Expr::Binary");
+        left.accept(self);
+        right.accept(self);
+        self.exit();
+    }
+
+    fn visit_grouping_expr(&mut self, expr: &Expr) {
+        self.enter("Expr::Grouping");
+        expr.accept(self);
+        self.exit();
+    }
+
+    fn visit_unary_expr(&mut self, _token: &Token, expr: &Expr) {
+        self.enter("Expr::Unary");
+        expr.accept(self);
+        self.exit();
+    }
+
+    fn visit_call_expr(&mut self, callee: &Expr, _token: &Token, args: &[Expr]) {
+        self.enter("Expr::Call");
+        callee.accept(self);
+        args.iter().for_each(|arg| arg.accept(self));
+        self.exit();
+    }
+
+    fn visit_conditional_expr(&mut self, cond: &Expr, then_branch: &Expr, else_branch: &Expr) {
+        self.enter("Expr::Conditional");
+        cond.accept(self);
+        then_branch.accept(self);
+        else_branch.accept(self);
+        self.exit();
+    }
+
+    fn visit_literal_expr_number(&mut self, _value: f64) {
+        self.leaf("Expr::Number");
+    }
+
+    fn visit_literal_expr_string(&mut self, _value: &str) {
+        self.leaf("Expr::String");
+    }
+
+    fn visit_literal_expr_boolean(&mut self, _value: bool) {
+        self.leaf("Expr::Boolean");
+    }
+
+    fn visit_literal_expr_nil(&mut self) {
+        self.leaf("Expr::Nil");
+    }
+
+    fn visit_variable_expr(&mut self, _token: &Token, _id: u64) {
+        self.leaf("Expr::Variable");
+    }
+
+    fn visit_assign_expr(&mut self, _token: &Token, expr: &Expr, _id: u64) {
+        self.enter("Expr::Assign");
+        expr.accept(self);
+        self.exit();
+    }
+
+    fn visit_logic_or(&mut self, left: &Expr, right: &Expr) {
+        self.enter("Expr::LogicOr");
+        left.accept(self);
+        right.accept(self);
+        self.exit();
+    }
+
+    fn visit_logic_xor(&mut self, left: &Expr, right: &Expr) {
+        self.enter("Expr::LogicXor");
+        left.accept(self);
+        right.accept(self);
+        self.exit();
+    }
+
+    fn visit_logic_and(&mut self, left: &Expr, right: &Expr) {
+        self.enter("Expr::LogicAnd");
+        left.accept(self);
+        right.accept(self);
+        self.exit();
+    }
+
+    fn visit_get_expr(&mut self, object: &Expr, _property: &Token) {
+        self.enter("Expr::Get");
+        object.accept(self);
+        self.exit();
+    }
+
+    fn visit_set_expr(&mut self, object: &Expr, _property: &Token, value: &Expr) {
+        self.enter("Expr::Set");
+        object.accept(self);
+        value.accept(self);
+        self.exit();
+    }
+
+    fn visit_compound_set_expr(
+        &mut self,
+        object: &Expr,
+        _property: &Token,
+        _operator: &Token,
+        value: &Expr,
+    ) {
+        self.enter("Expr::CompoundSet");
+        object.accept(self);
+        value.accept(self);
+        self.exit();
+    }
+
+    fn visit_this_expr(&mut self, _token: &Token, _id: u64) {
+        self.leaf("Expr::This");
+    }
+
+    fn visit_super_expr(&mut self, _keyword: &Token, _method: &Token, _id: u64) {
+        self.leaf("Expr::Super");
+    }
+
+    fn visit_lambda_expr(&mut self, _params: &[Token], body: &[Stmt]) {
+        self.enter("Expr::Lambda");
+        body.iter().for_each(|stmt| stmt.accept(self));
+        self.exit();
+    }
+
+    fn visit_map_expr(&mut self, _brace: &Token, entries: &[(Expr, Expr)]) {
+        self.enter("Expr::Map");
+        entries.iter().for_each(|(key, value)| {
+            key.accept(self);
+            value.accept(self);
+        });
+        self.exit();
+    }
+
+    fn visit_index_expr(&mut self, object: &Expr, _bracket: &Token, index: &Expr) {
+        self.enter("Expr::Index");
+        object.accept(self);
+        index.accept(self);
+        self.exit();
+    }
+
+    fn visit_index_set_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) {
+        self.enter("Expr::IndexSet");
+        object.accept(self);
+        index.accept(self);
+        value.accept(self);
+        self.exit();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lox;
+
+    #[test]
+    fn counts_small_program() {
+        let stmts = lox::run(
+            r#"
+            var a = 1 + 2;
+            fun double(x) {
+                return x * 2;
+            }
+            print double(a);
+            "#
+            .to_string(),
+        );
+
+        let stats = AstStats::new(&stmts);
+
+        assert_eq!(stats.count("Stmt::Var"), 1);
+        assert_eq!(stats.count("Stmt::Function"), 1);
+        assert_eq!(stats.count("Expr::Binary"), 2);
+        assert_eq!(stats.count("Expr::Number"), 3);
+        assert_eq!(stats.count("Expr::Variable"), 3);
+        assert_eq!(stats.count("Expr::Call"), 1);
+        assert_eq!(stats.total(), 13);
+        // Function -> Return -> Binary -> Variable
+        assert_eq!(stats.max_depth(), 4);
+    }
+}
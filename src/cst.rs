@@ -0,0 +1,212 @@
+use super::token::Token;
+use super::token_type::TokenType;
+use std::iter::Peekable;
+use std::slice::Iter;
+
+type Tokens<'a> = Peekable<Iter<'a, Token>>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CstKind {
+    Program,
+    Statement,
+    Block, // `{` ... `}`
+    Group, // `(` ... `)`
+}
+
+// Lossless syntax tree: every token given to `parse_cst`, trivia included, is kept in order,
+// so the source can be reproduced exactly. Scan with `Scanner::with_trivia` to keep
+// whitespace and comments.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CstNode {
+    Token(Token),
+    Node {
+        kind: CstKind,
+        children: Vec<CstNode>,
+    },
+}
+
+pub fn parse_cst(tokens: &[Token]) -> CstNode {
+    let mut tokens = tokens.iter().peekable();
+    let mut children = statements(&mut tokens, None);
+    children.extend(tokens.map(|token| CstNode::Token(token.clone())));
+
+    CstNode::Node {
+        kind: CstKind::Program,
+        children,
+    }
+}
+
+pub fn to_source(node: &CstNode) -> String {
+    match node {
+        CstNode::Token(token) => token.lexeme.clone(),
+        CstNode::Node { children, .. } => children.iter().map(to_source).collect(),
+    }
+}
+
+fn is_trivia(token: &Token) -> bool {
+    matches!(
+        token.kind,
+        TokenType::Whitespace | TokenType::Comment | TokenType::DocComment
+    )
+}
+
+fn statements(tokens: &mut Tokens, closing: Option<&TokenType>) -> Vec<CstNode> {
+    let mut children = vec![];
+
+    while let Some(token) = tokens.peek() {
+        if token.kind == TokenType::Eof || Some(&token.kind) == closing {
+            break;
+        }
+        // trivia between statements belongs to the enclosing node
+        if is_trivia(token) {
+            children.push(CstNode::Token(tokens.next().unwrap().clone()));
+            continue;
+        }
+        children.push(statement(tokens, closing));
+    }
+
+    children
+}
+
+fn statement(tokens: &mut Tokens, closing: Option<&TokenType>) -> CstNode {
+    let mut children = vec![];
+
+    while let Some(token) = tokens.peek() {
+        if token.kind == TokenType::Eof || Some(&token.kind) == closing {
+            break;
+        }
+
+        let token = tokens.next().unwrap();
+        match token.kind {
+            TokenType::Semicolon => {
+                children.push(CstNode::Token(token.clone()));
+                break;
+            }
+            TokenType::LeftParen => children.push(group(token, tokens)),
+            TokenType::LeftBrace => {
+                children.push(block(token, tokens));
+                if !continues_after_block(tokens) {
+                    break;
+                }
+            }
+            _ => children.push(CstNode::Token(token.clone())),
+        }
+    }
+
+    CstNode::Node {
+        kind: CstKind::Statement,
+        children,
+    }
+}
+
+// A block ends its statement unless the next token carries the statement on,
+// like `else` after an if body or `;` after an anonymous function
+fn continues_after_block(tokens: &Tokens) -> bool {
+    let next = tokens.clone().find(|token| !is_trivia(token));
+    matches!(
+        next.map(|token| &token.kind),
+        Some(
+            TokenType::Else
+                | TokenType::Semicolon
+                | TokenType::Comma
+                | TokenType::RightParen
+                | TokenType::Dot
+                | TokenType::LeftParen
+                | TokenType::LeftBracket
+        )
+    )
+}
+
+fn block(left_brace: &Token, tokens: &mut Tokens) -> CstNode {
+    let mut children = vec![CstNode::Token(left_brace.clone())];
+    children.extend(statements(tokens, Some(&TokenType::RightBrace)));
+    if let Some(right_brace) = tokens.next_if(|token| token.kind == TokenType::RightBrace) {
+        children.push(CstNode::Token(right_brace.clone()));
+    }
+
+    CstNode::Node {
+        kind: CstKind::Block,
+        children,
+    }
+}
+
+fn group(left_paren: &Token, tokens: &mut Tokens) -> CstNode {
+    let mut children = vec![CstNode::Token(left_paren.clone())];
+
+    while let Some(token) = tokens.next_if(|token| token.kind != TokenType::Eof) {
+        match token.kind {
+            TokenType::RightParen => {
+                children.push(CstNode::Token(token.clone()));
+                break;
+            }
+            TokenType::LeftParen => children.push(group(token, tokens)),
+            TokenType::LeftBrace => children.push(block(token, tokens)),
+            _ => children.push(CstNode::Token(token.clone())),
+        }
+    }
+
+    CstNode::Node {
+        kind: CstKind::Group,
+        children,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+
+    fn scan(source: &str) -> Vec<Token> {
+        let mut scanner = Scanner::new(source.into()).with_trivia(true);
+        scanner.scan_tokens();
+        scanner.tokens
+    }
+
+    #[test]
+    fn round_trip() {
+        let source = r#"// greeting
+var   name = "Lox \"world\"";  /* inline */
+fun greet(who) {
+    if (who != nil) { print "Hello " + who; }
+    else   print "Hello?";
+}
+var shout = fun (x) { return x; };
+greet( shout(name) );
+"#;
+
+        let cst = parse_cst(&scan(source));
+
+        assert_eq!(to_source(&cst), source);
+    }
+
+    #[test]
+    fn round_trip_with_tabs_and_invalid_escapes() {
+        let source = "var\ta = \"x\\q\\ty\";\t// c\n\tprint a;\r\n";
+
+        let cst = parse_cst(&scan(source));
+
+        assert_eq!(to_source(&cst), source);
+    }
+
+    #[test]
+    fn statements_are_nodes() {
+        let cst = parse_cst(&scan("var a = 1;\nfun f() { return a; }\nprint f();"));
+
+        let statements = match cst {
+            CstNode::Node { children, .. } => children
+                .into_iter()
+                .filter(|child| {
+                    matches!(
+                        child,
+                        CstNode::Node {
+                            kind: CstKind::Statement,
+                            ..
+                        }
+                    )
+                })
+                .count(),
+            CstNode::Token(_) => 0,
+        };
+        assert_eq!(statements, 3);
+    }
+}
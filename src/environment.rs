@@ -2,13 +2,14 @@ use crate::error::{LoxError, Result};
 use crate::object::Object;
 use crate::token::Token;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 
 #[derive(Debug)]
 pub struct Environment {
     variables: HashMap<String, Option<Object>>,
     enclosing: Option<Rc<RefCell<Environment>>>,
+    readonly: HashSet<String>, // defined by the host and never assigned by Lox code
 }
 
 impl Environment {
@@ -16,6 +17,7 @@ impl Environment {
         Environment {
             variables: HashMap::new(),
             enclosing: None,
+            readonly: HashSet::new(),
         }
     }
 
@@ -23,14 +25,34 @@ impl Environment {
         Environment {
             variables: HashMap::new(),
             enclosing: Some(enclosing),
+            readonly: HashSet::new(),
         }
     }
 
+    pub fn names(&self) -> Vec<String> {
+        self.variables.keys().cloned().collect()
+    }
+
     pub fn define(&mut self, key: String, value: Option<Object>) {
         self.variables.insert(key, value);
     }
 
+    pub fn define_readonly(&mut self, key: String, value: Object) {
+        self.readonly.insert(key.clone());
+        self.define(key, Some(value));
+    }
+
+    pub fn readonly_names(&self) -> Vec<String> {
+        self.readonly.iter().cloned().collect()
+    }
+
     pub fn assign(&mut self, token: &Token, value: Object) -> Result<()> {
+        if self.readonly.contains(&token.lexeme) {
+            return Err(LoxError::RuntimeError(
+                token.clone(),
+                format!("Can't assign to read-only variable '{}'", token.lexeme),
+            ));
+        }
         if self.variables.contains_key(&token.lexeme) {
             self.variables.insert(token.lexeme.clone(), Some(value));
             return Ok(());
@@ -1,13 +1,25 @@
 use crate::object::Object;
 use crate::token::Token;
+use crate::token_type::TokenType;
 use std::fmt::Display;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum LoxError {
     ResolverError(Token, String),
+    TypeError(Token, String),
     ParserError(usize, String),
+    ExpectedExpression(Token),
+    ExpectedToken {
+        expected: TokenType,
+        found: Token,
+        message: String,
+    },
     RuntimeError(Token, String),
+    Multiple(Vec<LoxError>),
     Return(Object),
+    Break(Token),
+    Continue(Token),
+    Exit(i32),
 }
 
 impl Display for LoxError {
@@ -16,15 +28,41 @@ impl Display for LoxError {
             LoxError::ResolverError(token, reason) => {
                 write!(f, "Resolver error in line {}: {}", token.line, reason)
             }
+            LoxError::TypeError(token, reason) => {
+                write!(f, "Type error in line {}: {}", token.line, reason)
+            }
             LoxError::ParserError(line, reason) => {
                 write!(f, "Parser error in line {}: {}", line, reason)
             }
+            LoxError::ExpectedExpression(found) => {
+                write!(
+                    f,
+                    "Parser error in line {}: Expected expression, found '{}'",
+                    found.line, found.lexeme
+                )
+            }
+            LoxError::ExpectedToken { found, message, .. } => {
+                write!(f, "Parser error in line {}: {}", found.line, message)
+            }
             LoxError::RuntimeError(token, message) => {
-                write!(f, "Runtime error: {} \n [line {}]", message, token.line)
+                write!(f, "Runtime error: {} \n [{}]", message, token.position())
+            }
+            LoxError::Multiple(errors) => {
+                let messages: Vec<String> = errors.iter().map(|err| err.to_string()).collect();
+                write!(f, "{}", messages.join("\n"))
             }
             LoxError::Return(_) => {
                 write!(f, "Return statement")
             }
+            LoxError::Break(token) => {
+                write!(f, "Break statement in line {}", token.line)
+            }
+            LoxError::Continue(token) => {
+                write!(f, "Continue statement in line {}", token.line)
+            }
+            LoxError::Exit(code) => {
+                write!(f, "Exit with code {}", code)
+            }
         }
     }
 }
@@ -1,3 +1,4 @@
+use super::stmt::Stmt;
 use super::token::Token;
 
 #[derive(Debug, Clone)]
@@ -9,12 +10,19 @@ pub enum Expr {
     Call(Box<Expr>, Token, Vec<Expr>),
     Get(Box<Expr>, Token), // Object and token name
     Set(Box<Expr>, Token, Box<Expr>),
+    CompoundSet(Box<Expr>, Token, Box<Token>, Box<Expr>), // object, field, operator and value of `a.b += c`
     This(Token, u64),
+    Super(Token, Token, u64),           // keyword and method name
+    Lambda(Vec<Token>, Vec<Stmt>),      // parameters and body
+    Map(Token, Vec<(Expr, Expr)>),      // opening brace and key-value pairs
+    Index(Box<Expr>, Token, Box<Expr>), // object, closing bracket and index
+    IndexSet(Box<Expr>, Token, Box<Expr>, Box<Expr>),
 
     // Variables
     Variable(Token, u64),
     Assign(Token, Box<Expr>, u64),
     LogicOr(Box<Expr>, Box<Expr>),
+    LogicXor(Box<Expr>, Box<Expr>),
     LogicAnd(Box<Expr>, Box<Expr>),
 
     // Literal values
@@ -39,6 +47,9 @@ impl Expr {
             Expr::Set(object, property_name, value) => {
                 visitor.visit_set_expr(object, property_name, value)
             }
+            Expr::CompoundSet(object, property_name, operator, value) => {
+                visitor.visit_compound_set_expr(object, property_name, operator, value)
+            }
             Expr::Conditional(expr, then_branch, else_branch) => visitor.visit_conditional_expr(
                 expr.as_ref(),
                 then_branch.as_ref(),
@@ -51,8 +62,16 @@ impl Expr {
             Expr::Variable(token, id) => visitor.visit_variable_expr(token, *id),
             Expr::Assign(token, expr, id) => visitor.visit_assign_expr(token, expr, *id),
             Expr::LogicOr(left, right) => visitor.visit_logic_or(left, right),
+            Expr::LogicXor(left, right) => visitor.visit_logic_xor(left, right),
             Expr::LogicAnd(left, right) => visitor.visit_logic_and(left, right),
             Expr::This(token, id) => visitor.visit_this_expr(token, *id),
+            Expr::Super(keyword, method, id) => visitor.visit_super_expr(keyword, method, *id),
+            Expr::Lambda(params, body) => visitor.visit_lambda_expr(params, body),
+            Expr::Map(brace, entries) => visitor.visit_map_expr(brace, entries),
+            Expr::Index(object, bracket, index) => visitor.visit_index_expr(object, bracket, index),
+            Expr::IndexSet(object, bracket, index, value) => {
+                visitor.visit_index_set_expr(object, bracket, index, value)
+            }
         }
     }
 }
@@ -70,8 +89,27 @@ pub trait Visitor<T> {
     fn visit_variable_expr(&mut self, token: &Token, id: u64) -> T;
     fn visit_assign_expr(&mut self, token: &Token, expr: &Expr, id: u64) -> T;
     fn visit_logic_or(&mut self, left: &Expr, right: &Expr) -> T;
+    fn visit_logic_xor(&mut self, left: &Expr, right: &Expr) -> T;
     fn visit_logic_and(&mut self, left: &Expr, right: &Expr) -> T;
     fn visit_get_expr(&mut self, object: &Expr, property: &Token) -> T;
     fn visit_set_expr(&mut self, object: &Expr, property: &Token, value: &Expr) -> T;
+    fn visit_compound_set_expr(
+        &mut self,
+        object: &Expr,
+        property: &Token,
+        operator: &Token,
+        value: &Expr,
+    ) -> T;
     fn visit_this_expr(&mut self, token: &Token, id: u64) -> T;
+    fn visit_super_expr(&mut self, keyword: &Token, method: &Token, id: u64) -> T;
+    fn visit_lambda_expr(&mut self, params: &[Token], body: &[Stmt]) -> T;
+    fn visit_map_expr(&mut self, brace: &Token, entries: &[(Expr, Expr)]) -> T;
+    fn visit_index_expr(&mut self, object: &Expr, bracket: &Token, index: &Expr) -> T;
+    fn visit_index_set_expr(
+        &mut self,
+        object: &Expr,
+        bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> T;
 }
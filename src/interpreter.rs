@@ -1,18 +1,23 @@
 use super::expr;
 use super::expr::Expr;
 use super::stmt;
-use super::stmt::{Function, Stmt};
+use super::stmt::{Case, Function, Signature, Stmt};
 use crate::environment::Environment;
 use crate::error::{LoxError, Result};
 use crate::lox;
 use crate::lox_callable::Callable;
 use crate::lox_class::LoxClass;
 use crate::lox_instance::LoxInstance;
-use crate::object::Object;
+use crate::object::{format_number, Object};
+use crate::parser::{ParseResult, Parser};
+use crate::primitive_method::PrimitiveMethod;
+use crate::resolver::Resolver;
+use crate::scanner::Scanner;
 use crate::token::Token;
 use crate::token_type::TokenType;
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
 use std::rc::Rc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -20,6 +25,26 @@ pub struct Interpreter {
     global_environment: Rc<RefCell<Environment>>,
     local_environment: Rc<RefCell<Environment>>,
     expr_id_scope_depth: HashMap<u64, u64>,
+    max_string_length: Option<usize>,
+    rng: Rng,
+    call_hints: bool, // hints about a statement that only looks up a method, like `obj.method;`
+    hints: Vec<String>,
+    input: Option<Box<dyn BufRead>>, // where `read_line` reads from, stdin when unset
+    clock: fn() -> f64,              // seconds returned by `clock`
+    print_separator: String,         // between the values of one `print`
+    print_terminator: String,        // after the last value
+    call_depth: usize,
+    max_call_depth: usize, // deeper calls are a runtime error instead of overflowing the stack
+}
+
+// A debug build uses up to about 35KB of stack for a call whose body nests several blocks and
+// groupings, so this many calls still fit in the 8MB stack of the main thread
+const MAX_CALL_DEPTH: usize = 200;
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Interpreter {
@@ -32,9 +57,97 @@ impl Interpreter {
             )))),
             global_environment,
             expr_id_scope_depth: HashMap::new(),
+            max_string_length: None,
+            call_hints: false,
+            hints: vec![],
+            input: None,
+            clock: wall_clock,
+            print_separator: " ".to_string(),
+            print_terminator: "\n".to_string(),
+            call_depth: 0,
+            max_call_depth: MAX_CALL_DEPTH,
+            rng: Rng::new(
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|time| time.as_nanos() as u64)
+                    .unwrap_or(0),
+            ),
         }
     }
 
+    // Strings longer than `limit` raise a runtime error instead of being allocated. Covers the
+    // operators and natives that build strings, but not `upper`, `lower` and `trim`, which can't
+    // make a string much longer than the one they're called on.
+    pub fn with_max_string_length(mut self, limit: usize) -> Self {
+        self.max_string_length = Some(limit);
+        self
+    }
+
+    pub fn with_call_hints(mut self, enabled: bool) -> Self {
+        self.call_hints = enabled;
+        self
+    }
+
+    pub fn with_input(mut self, input: impl BufRead + 'static) -> Self {
+        self.input = Some(Box::new(input));
+        self
+    }
+
+    // Replaces the wall clock behind `clock`, so programs using it can be run deterministically
+    pub fn with_clock(mut self, clock: fn() -> f64) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn with_max_call_depth(mut self, limit: usize) -> Self {
+        self.max_call_depth = limit;
+        self
+    }
+
+    pub fn set_print_separator(&mut self, separator: &str) {
+        self.print_separator = separator.to_string();
+    }
+
+    pub fn set_print_terminator(&mut self, terminator: &str) {
+        self.print_terminator = terminator.to_string();
+    }
+
+    // Hints gathered since the last call
+    pub fn take_hints(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.hints)
+    }
+
+    // Makes a host function callable from Lox code as a global named `name`
+    pub fn register_native(
+        &mut self,
+        name: &str,
+        arity: usize,
+        function: impl Fn(&[Object]) -> Result<Object> + 'static,
+    ) {
+        define_native(
+            &mut self.global_environment.borrow_mut(),
+            name,
+            arity,
+            function,
+        );
+    }
+
+    // A global scripts can read but neither assign nor shadow, like a configuration object
+    pub fn define_readonly(&mut self, name: &str, value: Object) {
+        self.global_environment
+            .borrow_mut()
+            .define_readonly(name.to_string(), value);
+    }
+
+    // Names defined by the host rather than by Lox code
+    pub fn global_names(&self) -> Vec<String> {
+        self.global_environment.borrow().names()
+    }
+
+    pub fn readonly_names(&self) -> Vec<String> {
+        self.global_environment.borrow().readonly_names()
+    }
+
     pub fn add_expr_ids_depth(&mut self, mut map: HashMap<u64, u64>) {
         map.drain().for_each(|(key, value)| {
             self.expr_id_scope_depth.insert(key, value);
@@ -45,17 +158,77 @@ impl Interpreter {
         Rc::clone(&self.local_environment)
     }
 
-    pub fn interpret(&mut self, statements: &[Stmt]) {
+    // Returns the exit code when the program called `exit`
+    pub fn interpret(&mut self, statements: &[Stmt]) -> Option<i32> {
         for stmt in statements {
-            stmt.accept(self)
-                .unwrap_or_else(|err| lox::report_runtime(err));
+            match stmt.accept(self) {
+                Ok(()) => {}
+                Err(LoxError::Exit(code)) => return Some(code),
+                Err(err) => lox::report_runtime(err),
+            }
+        }
+
+        None
+    }
+
+    // Runs `source` and returns the value of its final expression, or nil when the program
+    // doesn't end with one. Errors are returned instead of reported.
+    pub fn eval(&mut self, source: &str) -> Result<Object> {
+        let statements = parse_source(source)?;
+        let depth_map = Resolver::new()
+            .with_readonly(self.readonly_names())
+            .run(&statements)?;
+        self.add_expr_ids_depth(depth_map);
+
+        self.execute_for_value(&statements)
+    }
+
+    // Like `eval`, but runs in a temporary scope holding `bindings` that is dropped afterwards,
+    // so nothing the source declares outlives the call.
+    pub fn eval_in(&mut self, source: &str, bindings: &HashMap<String, Object>) -> Result<Object> {
+        let statements = parse_source(source)?;
+        let depth_map = Resolver::new()
+            .with_readonly(self.readonly_names())
+            .with_bindings(bindings.keys())
+            .run(&statements)?;
+        self.add_expr_ids_depth(depth_map);
+
+        let mut scope = Environment::new_with_enclosing(Rc::clone(&self.global_environment));
+        for (name, value) in bindings {
+            scope.define(name.clone(), Some(value.clone()));
+        }
+        let mut scope = Rc::new(RefCell::new(scope));
+
+        std::mem::swap(&mut self.local_environment, &mut scope);
+        let result = self.execute_for_value(&statements);
+        std::mem::swap(&mut self.local_environment, &mut scope);
+
+        result
+    }
+
+    // Value of the final statement when it's an expression, nil otherwise
+    fn execute_for_value(&mut self, statements: &[Stmt]) -> Result<Object> {
+        let (last, leading) = match statements.split_last() {
+            Some(split) => split,
+            None => return Ok(Object::Nil),
+        };
+        leading.iter().try_for_each(|stmt| self.execute(stmt))?;
+        match last {
+            Stmt::Expression(expr) => self.evaluate(expr),
+            stmt => self.execute(stmt).map(|_| Object::Nil),
         }
     }
 
-    pub fn print(&mut self, statement: &Stmt) {
+    pub fn print(&mut self, statement: &Stmt) -> Option<i32> {
         if let Stmt::Expression(x) = statement {
-            stmt::Visitor::visit_print_stmt(self, x).unwrap();
+            match stmt::Visitor::visit_print_stmt(self, std::slice::from_ref(x)) {
+                Ok(()) => {}
+                Err(LoxError::Exit(code)) => return Some(code),
+                Err(err) => lox::report_runtime(err),
+            }
         }
+
+        None
     }
 
     fn evaluate(&mut self, expr: &Expr) -> Result<Object> {
@@ -76,22 +249,141 @@ impl Interpreter {
         // After statements are executed. I swap it back
         std::mem::swap(&mut self.local_environment, &mut enclosing_environment);
 
-        let results: Result<()> = statements
-            .into_iter()
-            .map(|stmt| self.execute(stmt))
-            .collect();
+        let results: Result<()> = statements.iter().try_for_each(|stmt| self.execute(stmt));
 
         std::mem::swap(&mut self.local_environment, &mut enclosing_environment);
 
         results
     }
-}
 
-impl expr::Visitor<Result<Object>> for Interpreter {
-    fn visit_binary_expr(&mut self, left: &Expr, token: &Token, right: &Expr) -> Result<Object> {
-        let left = self.evaluate(left)?;
-        let right = self.evaluate(right)?;
+    // Instances may define an `is_truthy` method to decide how they behave in conditions.
+    // Like `toString`, it's only used when it takes no arguments.
+    fn is_truthy(&mut self, object: &Object) -> Result<bool> {
+        if let Object::ClassInstance(instance) = object {
+            let method = instance.borrow().class().find_method("is_truthy");
+            let method = method.filter(|method| method.arity() == 0);
+            if let Some(method) = method {
+                let result =
+                    self.call_hook("is_truthy", method.bind(Rc::clone(instance)).as_ref())?;
+                return Ok(result.is_truphy());
+            }
+        }
+
+        Ok(object.is_truphy())
+    }
+
+    // Instances may define a `toString` method to decide how they're printed and concatenated.
+    // One that takes arguments can't be called here, so the default format is used instead.
+    fn stringify(&mut self, object: &Object) -> Result<String> {
+        if let Object::ClassInstance(instance) = object {
+            let method = instance.borrow().class().find_method("toString");
+            let method = method.filter(|method| method.arity() == 0);
+            if let Some(method) = method {
+                let result =
+                    self.call_hook("toString", method.bind(Rc::clone(instance)).as_ref())?;
+                return Ok(result.to_string());
+            }
+        }
+
+        Ok(object.to_string())
+    }
+
+    // Every call made by Lox code goes through here, so runaway recursion is a runtime error at
+    // `token` instead of a stack overflow
+    fn call_with_depth(
+        &mut self,
+        token: &Token,
+        callable: &dyn Callable,
+        arguments: &[Object],
+    ) -> Result<Object> {
+        if self.call_depth >= self.max_call_depth {
+            return Err(LoxError::RuntimeError(
+                token.clone(),
+                "Stack overflow: maximum call depth exceeded".to_string(),
+            ));
+        }
+
+        self.call_depth += 1;
+        let result = callable.call(arguments, self);
+        self.call_depth -= 1;
+        result
+    }
+
+    // Hooks are called without a call expression, so there's no position to report errors at
+    fn call_hook(&mut self, name: &str, method: &dyn Callable) -> Result<Object> {
+        let token = Token::new(TokenType::Identifier, name.to_string(), 0);
+        self.call_with_depth(&token, method, &[])
+    }
+
+    // What `print` writes for `values`
+    fn format_print(&mut self, values: &[Object]) -> Result<String> {
+        let values: Result<Vec<String>> =
+            values.iter().map(|value| self.stringify(value)).collect();
+        Ok(values?.join(&self.print_separator) + &self.print_terminator)
+    }
+
+    fn get_property(&self, object: Object, property: &Token) -> Result<Object> {
+        match object {
+            Object::ClassInstance(instance) => LoxInstance::get(instance, property),
+            Object::String(_) | Object::Number(_) => PrimitiveMethod::get(&object, property),
+            _ => Err(LoxError::RuntimeError(
+                property.clone(),
+                "Only instances have properties".to_string(),
+            )),
+        }
+    }
+
+    fn check_string_length(&self, token: &Token, length: usize) -> Result<()> {
+        match self.max_string_length {
+            Some(limit) if length > limit => Err(LoxError::RuntimeError(
+                token.clone(),
+                format!("String length {} exceeds the limit of {}", length, limit),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn string_result(&self, token: &Token, value: String) -> Result<Object> {
+        self.check_string_length(token, value.chars().count())?;
+        Ok(Object::String(value))
+    }
+
+    // Like `string_result`, for strings built by the native `name`
+    fn native_string_result(&self, name: &str, value: String) -> Result<Object> {
+        let token = Token::new(TokenType::Identifier, name.to_string(), 0);
+        self.string_result(&token, value)
+    }
+
+    fn evaluate_map(
+        &mut self,
+        object: &Expr,
+        bracket: &Token,
+    ) -> Result<Rc<RefCell<HashMap<String, Object>>>> {
+        match self.evaluate(object)? {
+            Object::Map(map) => Ok(map),
+            _ => Err(LoxError::RuntimeError(
+                bracket.clone(),
+                "Only maps can be indexed".to_string(),
+            )),
+        }
+    }
+
+    fn evaluate_superclass(&mut self, superclass: &Expr) -> Result<LoxClass> {
+        let class = match self.evaluate(superclass)? {
+            Object::Call(callable) => callable.as_class().cloned(),
+            _ => None,
+        };
+
+        class.ok_or_else(|| match superclass {
+            Expr::Variable(token, _) => {
+                LoxError::RuntimeError(token.clone(), "Superclass must be a class".to_string())
+            }
+            _ => unreachable!(), // superclasses are always parsed as variables
+        })
+    }
 
+    // Applies a binary operator to operands that were already evaluated
+    fn binary(&mut self, token: &Token, left: Object, right: Object) -> Result<Object> {
         match (&token.kind, left, right) {
             //equality
             (TokenType::EqualEqual, left, right) => Ok(Object::Boolean(left == right)),
@@ -138,13 +430,21 @@ impl expr::Visitor<Result<Object>> for Interpreter {
                 Ok(Object::Number(left + right))
             }
             (TokenType::Plus, Object::String(left), Object::String(right)) => {
-                Ok(Object::String(format!("{}{}", left, right)))
+                self.string_result(token, format!("{}{}", left, right))
             }
             (TokenType::Plus, Object::Number(left), Object::String(right)) => {
-                Ok(Object::String(format!("{}{}", left, right)))
+                self.string_result(token, format!("{}{}", format_number(left), right))
             }
             (TokenType::Plus, Object::String(left), Object::Number(right)) => {
-                Ok(Object::String(format!("{}{}", left, right)))
+                self.string_result(token, format!("{}{}", left, format_number(right)))
+            }
+            (TokenType::Plus, Object::String(left), right @ Object::ClassInstance(_)) => {
+                let right = self.stringify(&right)?;
+                self.string_result(token, format!("{}{}", left, right))
+            }
+            (TokenType::Plus, left @ Object::ClassInstance(_), Object::String(right)) => {
+                let left = self.stringify(&left)?;
+                self.string_result(token, format!("{}{}", left, right))
             }
             (TokenType::Minus, Object::Number(left), Object::Number(right)) => {
                 Ok(Object::Number(left - right))
@@ -172,6 +472,31 @@ impl expr::Visitor<Result<Object>> for Interpreter {
             (TokenType::Slash, Object::Number(left), Object::Number(right)) => {
                 Ok(Object::Number(left / right))
             }
+            (TokenType::Star, Object::String(left), Object::Number(right)) => {
+                if right.fract() != 0.0 || right < 0.0 {
+                    return Err(LoxError::RuntimeError(
+                        token.clone(),
+                        "Expected string repetition count to be a whole number".into(),
+                    ));
+                }
+                // check before allocating so huge counts fail fast instead of aborting
+                let too_large = || {
+                    LoxError::RuntimeError(token.clone(), "String repetition is too large".into())
+                };
+                if right >= usize::MAX as f64 {
+                    return Err(too_large());
+                }
+                let count = right as usize;
+                let length = left.chars().count().checked_mul(count);
+                let bytes = left.len().checked_mul(count);
+                match (length, bytes) {
+                    (Some(length), Some(bytes)) if bytes <= isize::MAX as usize => {
+                        self.check_string_length(token, length)?;
+                        Ok(Object::String(left.repeat(count)))
+                    }
+                    _ => Err(too_large()),
+                }
+            }
 
             (TokenType::Star, _, _) | (TokenType::Slash, _, _) => Err(LoxError::RuntimeError(
                 token.clone(),
@@ -181,6 +506,14 @@ impl expr::Visitor<Result<Object>> for Interpreter {
             _ => unreachable!(),
         }
     }
+}
+
+impl expr::Visitor<Result<Object>> for Interpreter {
+    fn visit_binary_expr(&mut self, left: &Expr, token: &Token, right: &Expr) -> Result<Object> {
+        let left = self.evaluate(left)?;
+        let right = self.evaluate(right)?;
+        self.binary(token, left, right)
+    }
 
     fn visit_grouping_expr(&mut self, expr: &Expr) -> Result<Object> {
         self.evaluate(expr)
@@ -189,7 +522,7 @@ impl expr::Visitor<Result<Object>> for Interpreter {
     fn visit_unary_expr(&mut self, token: &Token, expr: &Expr) -> Result<Object> {
         let eval = self.evaluate(expr)?;
         match (&token.kind, eval) {
-            (TokenType::Bang, x) => Ok(Object::Boolean(!x.is_truphy())),
+            (TokenType::Bang, x) => Ok(Object::Boolean(!self.is_truthy(&x)?)),
             (TokenType::Minus, Object::Number(value)) => Ok(Object::Number(-value)),
             (TokenType::Minus, _) => Err(LoxError::RuntimeError(
                 token.clone(),
@@ -206,7 +539,7 @@ impl expr::Visitor<Result<Object>> for Interpreter {
         else_branch: &Expr,
     ) -> Result<Object> {
         let cond = self.evaluate(cond)?;
-        if cond.is_truphy() {
+        if self.is_truthy(&cond)? {
             self.evaluate(then_branch)
         } else {
             self.evaluate(else_branch)
@@ -261,17 +594,27 @@ impl expr::Visitor<Result<Object>> for Interpreter {
     fn visit_logic_or(&mut self, left: &Expr, right: &Expr) -> Result<Object> {
         let left = self.evaluate(left)?;
 
-        if left.is_truphy() {
+        if self.is_truthy(&left)? {
             Ok(left)
         } else {
             self.evaluate(right)
         }
     }
 
+    // unlike `and` and `or` both sides are always evaluated and the result is a boolean
+    fn visit_logic_xor(&mut self, left: &Expr, right: &Expr) -> Result<Object> {
+        let left = self.evaluate(left)?;
+        let right = self.evaluate(right)?;
+
+        Ok(Object::Boolean(
+            self.is_truthy(&left)? ^ self.is_truthy(&right)?,
+        ))
+    }
+
     fn visit_logic_and(&mut self, left: &Expr, right: &Expr) -> Result<Object> {
         let left = self.evaluate(left)?;
 
-        if !left.is_truphy() {
+        if !self.is_truthy(&left)? {
             Ok(left)
         } else {
             self.evaluate(right)
@@ -281,8 +624,7 @@ impl expr::Visitor<Result<Object>> for Interpreter {
     fn visit_call_expr(&mut self, callee: &Expr, token: &Token, args: &[Expr]) -> Result<Object> {
         let callee = self.evaluate(callee)?;
 
-        let arguments: Result<Vec<Object>> =
-            args.into_iter().map(|arg| self.evaluate(arg)).collect();
+        let arguments: Result<Vec<Object>> = args.iter().map(|arg| self.evaluate(arg)).collect();
         let arguments = arguments?;
 
         let callable = if let Object::Call(callable) = callee {
@@ -304,29 +646,52 @@ impl expr::Visitor<Result<Object>> for Interpreter {
                 ),
             ));
         }
-        callable.call(&arguments, self)
+        // errors raised outside of Lox code, like in natives, have no position of their own
+        self.call_with_depth(token, callable.as_ref(), &arguments)
+            .map_err(|err| match err {
+                LoxError::RuntimeError(origin, message) if origin.line == 0 => {
+                    let origin = Token {
+                        line: token.line,
+                        column: token.column,
+                        ..origin
+                    };
+                    LoxError::RuntimeError(origin, message)
+                }
+                err => err,
+            })
     }
 
     fn visit_get_expr(&mut self, object: &Expr, property: &Token) -> Result<Object> {
         let object = self.evaluate(object)?;
+        self.get_property(object, property)
+    }
+
+    fn visit_set_expr(&mut self, object: &Expr, property: &Token, value: &Expr) -> Result<Object> {
+        let object = self.evaluate(object)?;
 
-        let instance = if let Object::ClassInstance(instance) = object {
+        let object = if let Object::ClassInstance(instance) = object {
             instance
         } else {
             return Err(LoxError::RuntimeError(
                 property.clone(),
-                "Only instances have properties".to_string(),
+                "Only instances have fields".to_string(),
             ));
         };
 
-        let value = LoxInstance::get(instance, property);
-        value
-    }
+        let value = self.evaluate(value)?;
+        object.borrow_mut().set(property.clone(), value.clone());
 
-    fn visit_set_expr(&mut self, object: &Expr, property: &Token, value: &Expr) -> Result<Object> {
-        let object = self.evaluate(object)?;
+        Ok(value)
+    }
 
-        let object = if let Object::ClassInstance(instance) = object {
+    fn visit_compound_set_expr(
+        &mut self,
+        object: &Expr,
+        property: &Token,
+        operator: &Token,
+        value: &Expr,
+    ) -> Result<Object> {
+        let object = if let Object::ClassInstance(instance) = self.evaluate(object)? {
             instance
         } else {
             return Err(LoxError::RuntimeError(
@@ -335,7 +700,9 @@ impl expr::Visitor<Result<Object>> for Interpreter {
             ));
         };
 
+        let current = LoxInstance::get(Rc::clone(&object), property)?;
         let value = self.evaluate(value)?;
+        let value = self.binary(operator, current, value)?;
         object.borrow_mut().set(property.clone(), value.clone());
 
         Ok(value)
@@ -345,6 +712,86 @@ impl expr::Visitor<Result<Object>> for Interpreter {
         let distance = self.expr_id_scope_depth.get(&id).unwrap(); //there is always an id for `this` expressions
         self.local_environment.borrow_mut().get_at(token, *distance)
     }
+
+    fn visit_lambda_expr(&mut self, params: &[Token], body: &[Stmt]) -> Result<Object> {
+        Ok(Object::Call(Box::new(UserFunction::new(
+            Vec::from(params),
+            Vec::from(body),
+            self.environment(),
+            false,
+        ))))
+    }
+
+    fn visit_map_expr(&mut self, brace: &Token, entries: &[(Expr, Expr)]) -> Result<Object> {
+        let mut map = HashMap::new();
+        for (key, value) in entries {
+            let key = match self.evaluate(key)? {
+                Object::String(key) => key,
+                _ => return Err(map_key_error(brace)),
+            };
+            map.insert(key, self.evaluate(value)?);
+        }
+
+        Ok(Object::Map(Rc::new(RefCell::new(map))))
+    }
+
+    fn visit_index_expr(&mut self, object: &Expr, bracket: &Token, index: &Expr) -> Result<Object> {
+        let map = self.evaluate_map(object, bracket)?;
+        let key = match self.evaluate(index)? {
+            Object::String(key) => key,
+            _ => return Err(map_key_error(bracket)),
+        };
+
+        let value = map.borrow().get(&key).cloned();
+        Ok(value.unwrap_or(Object::Nil))
+    }
+
+    fn visit_index_set_expr(
+        &mut self,
+        object: &Expr,
+        bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> Result<Object> {
+        let map = self.evaluate_map(object, bracket)?;
+        let key = match self.evaluate(index)? {
+            Object::String(key) => key,
+            _ => return Err(map_key_error(bracket)),
+        };
+
+        let value = self.evaluate(value)?;
+        map.borrow_mut().insert(key, value.clone());
+        Ok(value)
+    }
+
+    fn visit_super_expr(&mut self, keyword: &Token, method: &Token, id: u64) -> Result<Object> {
+        let distance = *self.expr_id_scope_depth.get(&id).unwrap(); //there is always an id for `super` expressions
+        let superclass = self.local_environment.borrow().get_at(keyword, distance)?;
+        // `this` is always bound in the environment right inside the one holding `super`
+        let instance = self
+            .local_environment
+            .borrow()
+            .get_at(&this_token(), distance - 1)?;
+
+        let (superclass, instance) = match (superclass, instance) {
+            (Object::Call(superclass), Object::ClassInstance(instance)) => (superclass, instance),
+            _ => unreachable!(), // resolver only allows `super` inside subclass methods
+        };
+
+        let function = superclass
+            .as_class()
+            .and_then(|superclass| superclass.find_method(&method.lexeme));
+        match function {
+            Some(function) => Ok(Object::Call(function.bind(instance))),
+            // fields live on the instance, so `super.field` reads the same field as `this.field`
+            None => instance.borrow().field(&method.lexeme).ok_or_else(|| {
+                LoxError::RuntimeError(
+                    method.clone(),
+                    format!("Undefined property '{}'", method.lexeme),
+                )
+            }),
+        }
+    }
 }
 
 impl stmt::Visitor<Result<()>> for Interpreter {
@@ -354,19 +801,38 @@ impl stmt::Visitor<Result<()>> for Interpreter {
     }
 
     fn visit_expression_stmt(&mut self, expr: &Expr) -> Result<()> {
-        self.evaluate(expr)?;
-
-        Ok(())
+        match expr {
+            // only methods declared on the class are hinted about, a field holding a function
+            // may well be looked up on purpose
+            Expr::Get(object, property) if self.call_hints => {
+                let object = self.evaluate(object)?;
+                if let Object::ClassInstance(instance) = &object {
+                    if instance.borrow().is_method(&property.lexeme) {
+                        self.hints.push(format!(
+                            "[line {}] Hint: '{}' is a method and wasn't called, did you mean '{}()'?",
+                            property.line, property.lexeme, property.lexeme
+                        ));
+                    }
+                }
+                self.get_property(object, property).map(|_| ())
+            }
+            _ => self.evaluate(expr).map(|_| ()),
+        }
     }
 
-    fn visit_print_stmt(&mut self, expr: &Expr) -> Result<()> {
-        let value = self.evaluate(expr)?;
+    fn visit_print_stmt(&mut self, values: &[Expr]) -> Result<()> {
+        let values: Result<Vec<Object>> = values.iter().map(|value| self.evaluate(value)).collect();
 
-        println!("{}", value);
+        print!("{}", self.format_print(&values?)?);
         Ok(())
     }
 
-    fn visit_var_stmt(&mut self, token: &Token, expr: Option<&Expr>) -> Result<()> {
+    fn visit_var_stmt(
+        &mut self,
+        token: &Token,
+        _: Option<&Token>,
+        expr: Option<&Expr>,
+    ) -> Result<()> {
         let value = expr.as_ref().map(|value| self.evaluate(value));
 
         let value = match value {
@@ -390,7 +856,7 @@ impl stmt::Visitor<Result<()>> for Interpreter {
     ) -> Result<()> {
         let cond = self.evaluate(cond)?;
 
-        if cond.is_truphy() {
+        if self.is_truthy(&cond)? {
             self.execute(then_branch)
         } else if let Some(else_branch) = else_branch {
             self.execute(else_branch)
@@ -399,39 +865,123 @@ impl stmt::Visitor<Result<()>> for Interpreter {
         }
     }
 
-    fn visit_while_stmt(&mut self, cond: &Expr, block: &Stmt) -> Result<()> {
-        while self.evaluate(cond)?.is_truphy() {
-            self.execute(block)?;
+    fn visit_while_stmt(
+        &mut self,
+        cond: &Expr,
+        block: &Stmt,
+        increment: Option<&Expr>,
+        else_branch: Option<&Stmt>,
+    ) -> Result<()> {
+        loop {
+            let cond = self.evaluate(cond)?;
+            if !self.is_truthy(&cond)? {
+                break;
+            }
+
+            match self.execute(block) {
+                Err(LoxError::Break(_)) => return Ok(()), // else branch only runs when the loop wasn't broken
+                Err(LoxError::Continue(_)) => {}
+                result => result?,
+            }
+
+            if let Some(increment) = increment {
+                self.evaluate(increment)?;
+            }
         }
 
-        Ok(())
+        match else_branch {
+            Some(else_branch) => self.execute(else_branch),
+            None => Ok(()),
+        }
     }
 
-    fn visit_function_stmt(&mut self, name: &Token, params: &[Token], body: &[Stmt]) -> Result<()> {
-        self.local_environment.borrow_mut().define(
-            name.lexeme.clone(),
-            Some(Object::Call(Box::new(UserFunction::new(
-                Vec::from(params),
-                Vec::from(body),
-                self.environment(),
-                false,
-            )))),
-        );
+    fn visit_function_stmt(
+        &mut self,
+        name: &Token,
+        params: &[Token],
+        body: &[Stmt],
+        signature: &Signature,
+    ) -> Result<()> {
+        let function = UserFunction::new(
+            Vec::from(params),
+            Vec::from(body),
+            self.environment(),
+            false,
+        )
+        .with_doc(signature.doc.clone());
+        self.local_environment
+            .borrow_mut()
+            .define(name.lexeme.clone(), Some(Object::Call(Box::new(function))));
         Ok(())
     }
 
-    fn visit_return_stmt(&mut self, _token: &Token, expr: &Expr) -> Result<()> {
-        let value = self.evaluate(expr)?;
+    fn visit_switch_stmt(
+        &mut self,
+        value: &Expr,
+        cases: &[Case],
+        default: Option<&[Stmt]>,
+    ) -> Result<()> {
+        let value = self.evaluate(value)?;
+
+        let mut matched = default;
+        for (case, body) in cases {
+            if self.evaluate(case)? == value {
+                matched = Some(body);
+                break;
+            }
+        }
+
+        match matched {
+            Some(body) => {
+                let enclosed_enviroment = Environment::new_with_enclosing(self.environment());
+                self.execute_block(body, enclosed_enviroment)
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn visit_return_stmt(&mut self, _token: &Token, expr: Option<&Expr>) -> Result<()> {
+        let value = match expr {
+            Some(expr) => self.evaluate(expr)?,
+            None => Object::Nil,
+        };
         Err(LoxError::Return(value))
     }
 
-    fn visit_class_stmt(&mut self, token: &Token, methods: &[Function]) -> Result<()> {
+    fn visit_break_stmt(&mut self, token: &Token) -> Result<()> {
+        Err(LoxError::Break(token.clone()))
+    }
+
+    fn visit_continue_stmt(&mut self, token: &Token) -> Result<()> {
+        Err(LoxError::Continue(token.clone()))
+    }
+
+    fn visit_class_stmt(
+        &mut self,
+        token: &Token,
+        superclass: Option<&Expr>,
+        methods: &[Function],
+    ) -> Result<()> {
+        let superclass = match superclass {
+            Some(superclass) => Some(Box::new(self.evaluate_superclass(superclass)?)),
+            None => None,
+        };
+
         self.local_environment
             .borrow_mut()
             .define(token.lexeme.clone(), None);
 
+        let method_environment = match &superclass {
+            Some(superclass) => {
+                let mut environment = Environment::new_with_enclosing(self.environment());
+                environment.define("super".to_string(), Some(Object::Call(superclass.clone())));
+                Rc::new(RefCell::new(environment))
+            }
+            None => self.environment(),
+        };
+
         let methods: HashMap<String, UserFunction> = methods
-            .into_iter()
+            .iter()
             .cloned()
             .map(|function| {
                 (
@@ -439,13 +989,14 @@ impl stmt::Visitor<Result<()>> for Interpreter {
                     UserFunction::new(
                         function.1,
                         function.2,
-                        Rc::clone(&self.local_environment),
+                        Rc::clone(&method_environment),
                         function.0.lexeme == "init",
-                    ),
+                    )
+                    .with_doc(function.3.doc),
                 )
             })
             .collect();
-        let class = LoxClass::new(token.clone(), methods);
+        let class = LoxClass::new(token.clone(), superclass, methods);
         self.local_environment
             .borrow_mut()
             .assign(token, Object::Call(Box::new(class)))?;
@@ -455,30 +1006,731 @@ impl stmt::Visitor<Result<()>> for Interpreter {
 }
 fn create_global_enviroment() -> Environment {
     let mut global_environment = Environment::new();
+    global_environment.define(
+        "str".to_string(),
+        Some(Object::Call(Box::new(StrFunction {}))),
+    );
+    global_environment.define(
+        "write".to_string(),
+        Some(Object::Call(Box::new(WriteFunction {}))),
+    );
+    // padding, natives have a fixed arity so a nil fill means a space
+    global_environment.define(
+        "pad_left".to_string(),
+        Some(Object::Call(Box::new(PadFunction {
+            name: "pad_left",
+            left: true,
+        }))),
+    );
+    global_environment.define(
+        "pad_right".to_string(),
+        Some(Object::Call(Box::new(PadFunction {
+            name: "pad_right",
+            left: false,
+        }))),
+    );
     global_environment.define(
         "clock".to_string(),
         Some(Object::Call(Box::new(ClockFunction {}))),
     );
+    global_environment.define(
+        "commafy".to_string(),
+        Some(Object::Call(Box::new(CommafyFunction {}))),
+    );
+    global_environment.define(
+        "exit".to_string(),
+        Some(Object::Call(Box::new(ExitFunction {}))),
+    );
+    global_environment.define(
+        "equals_ignore_case".to_string(),
+        Some(Object::Call(Box::new(EqualsIgnoreCaseFunction {}))),
+    );
+    global_environment.define(
+        "seed".to_string(),
+        Some(Object::Call(Box::new(SeedFunction {}))),
+    );
+    global_environment.define(
+        "random".to_string(),
+        Some(Object::Call(Box::new(RandomFunction {}))),
+    );
+    global_environment.define(
+        "random_int".to_string(),
+        Some(Object::Call(Box::new(RandomIntFunction {}))),
+    );
+    global_environment.define(
+        "lazy".to_string(),
+        Some(Object::Call(Box::new(LazyFunction {}))),
+    );
+    global_environment.define(
+        "force".to_string(),
+        Some(Object::Call(Box::new(ForceFunction {}))),
+    );
+    global_environment.define(
+        "times".to_string(),
+        Some(Object::Call(Box::new(TimesFunction {}))),
+    );
+    global_environment.define(
+        "read_line".to_string(),
+        Some(Object::Call(Box::new(ReadLineFunction {}))),
+    );
+    global_environment.define(
+        "pow_mod".to_string(),
+        Some(Object::Call(Box::new(PowModFunction {}))),
+    );
 
-    global_environment
-}
-
-// global functions
+    define_native(
+        &mut global_environment,
+        "len",
+        1,
+        |arguments| match &arguments[0] {
+            Object::String(value) => Ok(Object::Number(value.chars().count() as f64)),
+            _ => Err(native_error("len", "Expected argument to be a string")),
+        },
+    );
 
-#[derive(Clone, Debug)]
-struct ClockFunction {}
-impl Callable for ClockFunction {
-    fn arity(&self) -> usize {
-        0
-    }
+    define_native(&mut global_environment, "type", 1, |arguments| {
+        let name = match &arguments[0] {
+            Object::Number(_) => "number",
+            Object::String(_) => "string",
+            Object::Boolean(_) => "boolean",
+            Object::Nil => "nil",
+            Object::Call(callable) if callable.as_class().is_some() => "class",
+            Object::Call(_) => "function",
+            Object::ClassInstance(_) => "instance",
+            Object::Map(_) => "map",
+            Object::WeakRef(_) => "weak_ref",
+        };
+        Ok(Object::String(name.to_string()))
+    });
+
+    define_native(
+        &mut global_environment,
+        "doc",
+        1,
+        |arguments| match &arguments[0] {
+            Object::Call(callable) => Ok(callable
+                .doc()
+                .map(|doc| Object::String(doc.to_string()))
+                .unwrap_or(Object::Nil)),
+            _ => Err(native_error("doc", "Expected argument to be a function")),
+        },
+    );
 
-    fn call(&self, _: &[Object], _: &mut Interpreter) -> Result<Object> {
-        let start = SystemTime::now();
-        let since_the_epoch = start
-            .duration_since(UNIX_EPOCH)
-            .expect("Time went backwards");
-        Ok(Object::Number(since_the_epoch.as_secs_f64()))
-    }
+    define_native(&mut global_environment, "compose", 2, |arguments| {
+        match (&arguments[0], &arguments[1]) {
+            (Object::Call(outer), Object::Call(inner)) if outer.arity() == 1 => {
+                Ok(Object::Call(Box::new(ComposedFn {
+                    outer: outer.clone(),
+                    inner: inner.clone(),
+                })))
+            }
+            _ => Err(native_error(
+                "compose",
+                "Expected two functions, the first taking one argument",
+            )),
+        }
+    });
+
+    // weak references
+    define_native(
+        &mut global_environment,
+        "weak_ref",
+        1,
+        |arguments| match &arguments[0] {
+            Object::ClassInstance(instance) => Ok(Object::WeakRef(Rc::downgrade(instance))),
+            _ => Err(native_error(
+                "weak_ref",
+                "Expected argument to be an instance",
+            )),
+        },
+    );
+    define_native(
+        &mut global_environment,
+        "deref",
+        1,
+        |arguments| match &arguments[0] {
+            Object::WeakRef(weak) => Ok(weak
+                .upgrade()
+                .map(Object::ClassInstance)
+                .unwrap_or(Object::Nil)),
+            _ => Err(native_error(
+                "deref",
+                "Expected argument to be a weak reference",
+            )),
+        },
+    );
+
+    // conversion
+    define_native(&mut global_environment, "parse_int", 2, |arguments| {
+        let radix = whole_number_argument("parse_int", &arguments[1])?;
+        if !(2..=36).contains(&radix) {
+            return Err(native_error(
+                "parse_int",
+                "Expected radix to be between 2 and 36",
+            ));
+        }
+        match &arguments[0] {
+            Object::String(value) => Ok(i64::from_str_radix(value, radix as u32)
+                .map(|number| Object::Number(number as f64))
+                .unwrap_or(Object::Nil)),
+            _ => Err(native_error(
+                "parse_int",
+                "Expected argument to be a string",
+            )),
+        }
+    });
+
+    define_native(&mut global_environment, "num", 1, |arguments| {
+        let parsed = match &arguments[0] {
+            Object::String(value) => value.trim().parse::<f64>().ok(),
+            _ => return Err(native_error("num", "Expected argument to be a string")),
+        };
+        parsed
+            .filter(|number| number.is_finite())
+            .map(Object::Number)
+            .ok_or_else(|| native_error("num", "Expected string to contain a number"))
+    });
+
+    // math
+    let unary_math = [
+        ("sqrt", f64::sqrt as fn(f64) -> f64),
+        ("floor", f64::floor),
+        ("ceil", f64::ceil),
+        ("abs", f64::abs),
+    ];
+    for (name, operation) in unary_math {
+        define_native(&mut global_environment, name, 1, move |arguments| {
+            let value = number_argument(name, &arguments[0])?;
+            Ok(Object::Number(operation(value)))
+        });
+    }
+    define_native(&mut global_environment, "pow", 2, |arguments| {
+        let base = number_argument("pow", &arguments[0])?;
+        let exponent = number_argument("pow", &arguments[1])?;
+        Ok(Object::Number(base.powf(exponent)))
+    });
+    define_native(&mut global_environment, "clamp", 3, |arguments| {
+        let value = number_argument("clamp", &arguments[0])?;
+        let low = number_argument("clamp", &arguments[1])?;
+        let high = number_argument("clamp", &arguments[2])?;
+        if low > high {
+            return Err(native_error(
+                "clamp",
+                "Expected lower bound to be less than or equal to upper bound",
+            ));
+        }
+        Ok(Object::Number(value.max(low).min(high)))
+    });
+    define_native(&mut global_environment, "lerp", 3, |arguments| {
+        let start = number_argument("lerp", &arguments[0])?;
+        let end = number_argument("lerp", &arguments[1])?;
+        let t = number_argument("lerp", &arguments[2])?;
+        Ok(Object::Number(start + (end - start) * t))
+    });
+
+    global_environment
+}
+
+fn parse_source(source: &str) -> Result<Vec<Stmt>> {
+    let mut scanner = Scanner::new(source.to_string());
+    scanner.scan_tokens();
+    match Parser::new(&scanner.tokens, true).parse() {
+        ParseResult::List(statements) => statements.into_iter().collect(),
+        ParseResult::SingleExpr(statement) => Ok(vec![statement?]),
+        ParseResult::Incomplete => unreachable!(), // incomplete input detection is off
+    }
+}
+
+fn define_native(
+    environment: &mut Environment,
+    name: &str,
+    arity: usize,
+    function: impl Fn(&[Object]) -> Result<Object> + 'static,
+) {
+    let native = NativeFunction {
+        name: name.to_string(),
+        arity,
+        function: Rc::new(function),
+    };
+    environment.define(name.to_string(), Some(Object::Call(Box::new(native))));
+}
+
+fn number_argument(name: &str, argument: &Object) -> Result<f64> {
+    match argument {
+        Object::Number(value) => Ok(*value),
+        _ => Err(native_error(name, "Expected argument to be a number")),
+    }
+}
+
+// global functions
+
+type NativeFn = dyn Fn(&[Object]) -> Result<Object>;
+
+#[derive(Clone)]
+struct NativeFunction {
+    name: String,
+    arity: usize,
+    function: Rc<NativeFn>,
+}
+impl std::fmt::Debug for NativeFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "NativeFunction({})", self.name)
+    }
+}
+impl Callable for NativeFunction {
+    fn arity(&self) -> usize {
+        self.arity
+    }
+
+    fn call(&self, arguments: &[Object], _: &mut Interpreter) -> Result<Object> {
+        (self.function)(arguments)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ClockFunction {}
+impl Callable for ClockFunction {
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn call(&self, _: &[Object], interpreter: &mut Interpreter) -> Result<Object> {
+        Ok(Object::Number((interpreter.clock)()))
+    }
+}
+
+fn wall_clock() -> f64 {
+    let start = SystemTime::now();
+    let since_the_epoch = start
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards");
+    since_the_epoch.as_secs_f64()
+}
+
+#[derive(Clone, Debug)]
+struct CommafyFunction {}
+impl Callable for CommafyFunction {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(&self, arguments: &[Object], interpreter: &mut Interpreter) -> Result<Object> {
+        let value = match &arguments[0] {
+            Object::Number(value) => *value,
+            _ => return Err(native_error("commafy", "Expected argument to be a number")),
+        };
+
+        let text = value.abs().to_string();
+        let (integer, fraction) = match text.find('.') {
+            Some(index) => text.split_at(index),
+            None => (text.as_str(), ""),
+        };
+
+        let mut grouped = String::new();
+        for (index, digit) in integer.chars().enumerate() {
+            if index > 0 && (integer.len() - index) % 3 == 0 {
+                grouped.push(',');
+            }
+            grouped.push(digit);
+        }
+
+        let sign = if value < 0.0 { "-" } else { "" };
+        interpreter.native_string_result("commafy", format!("{}{}{}", sign, grouped, fraction))
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ExitFunction {}
+impl Callable for ExitFunction {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(&self, arguments: &[Object], _: &mut Interpreter) -> Result<Object> {
+        match arguments[0] {
+            Object::Number(code) if code.fract() == 0.0 && (0.0..=255.0).contains(&code) => {
+                Err(LoxError::Exit(code as i32))
+            }
+            _ => Err(native_error(
+                "exit",
+                "Expected exit code to be a whole number between 0 and 255",
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct EqualsIgnoreCaseFunction {}
+impl Callable for EqualsIgnoreCaseFunction {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(&self, arguments: &[Object], _: &mut Interpreter) -> Result<Object> {
+        match (&arguments[0], &arguments[1]) {
+            (Object::String(left), Object::String(right)) => {
+                Ok(Object::Boolean(left.to_lowercase() == right.to_lowercase()))
+            }
+            _ => Err(native_error(
+                "equals_ignore_case",
+                "Expected arguments to be strings",
+            )),
+        }
+    }
+}
+
+// xorshift64* generator, reproducible for a given seed
+struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // splitmix64 spreads the seed's bits and keeps the state away from zero
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        Rng {
+            state: if z == 0 { 1 } else { z },
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    // uniform in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+fn whole_number_argument(name: &str, argument: &Object) -> Result<i64> {
+    match argument {
+        Object::Number(x) if x.fract() == 0.0 && x.abs() <= i64::MAX as f64 => Ok(*x as i64),
+        _ => Err(native_error(name, "Expected argument to be a whole number")),
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ReadLineFunction {}
+impl Callable for ReadLineFunction {
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn call(&self, _: &[Object], interpreter: &mut Interpreter) -> Result<Object> {
+        let mut line = String::new();
+        let read = match interpreter.input.as_mut() {
+            Some(input) => input.read_line(&mut line),
+            None => io::stdin().read_line(&mut line),
+        };
+        match read {
+            Ok(0) => Ok(Object::Nil),
+            Ok(_) => {
+                if line.ends_with('\n') {
+                    line.pop();
+                    if line.ends_with('\r') {
+                        line.pop();
+                    }
+                }
+                interpreter.native_string_result("read_line", line)
+            }
+            Err(err) => Err(native_error("read_line", &err.to_string())),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SeedFunction {}
+impl Callable for SeedFunction {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(&self, arguments: &[Object], interpreter: &mut Interpreter) -> Result<Object> {
+        let seed = whole_number_argument("seed", &arguments[0])?;
+        interpreter.rng = Rng::new(seed as u64);
+        Ok(Object::Nil)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct RandomFunction {}
+impl Callable for RandomFunction {
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn call(&self, _: &[Object], interpreter: &mut Interpreter) -> Result<Object> {
+        Ok(Object::Number(interpreter.rng.next_f64()))
+    }
+}
+
+#[derive(Clone, Debug)]
+struct RandomIntFunction {}
+impl Callable for RandomIntFunction {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    // both bounds are inclusive
+    fn call(&self, arguments: &[Object], interpreter: &mut Interpreter) -> Result<Object> {
+        let low = whole_number_argument("random_int", &arguments[0])?;
+        let high = whole_number_argument("random_int", &arguments[1])?;
+        if low > high {
+            return Err(native_error(
+                "random_int",
+                "Expected lower bound to be less than or equal to upper bound",
+            ));
+        }
+
+        let span = (high as i128 - low as i128 + 1) as u128;
+        let offset = (interpreter.rng.next_u64() as u128 % span) as i128;
+        Ok(Object::Number((low as i128 + offset) as f64))
+    }
+}
+
+#[derive(Clone, Debug)]
+struct LazyFunction {}
+impl Callable for LazyFunction {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(&self, arguments: &[Object], _: &mut Interpreter) -> Result<Object> {
+        match &arguments[0] {
+            Object::Call(function) if function.arity() == 0 => Ok(Object::Call(Box::new(Thunk {
+                function: function.clone(),
+                cache: Rc::new(RefCell::new(None)),
+            }))),
+            _ => Err(native_error(
+                "lazy",
+                "Expected argument to be a function without parameters",
+            )),
+        }
+    }
+}
+
+// Runs its function on the first call and returns the cached result afterwards.
+// Copies of a thunk share the cache.
+#[derive(Clone, Debug)]
+struct Thunk {
+    function: Box<dyn Callable>,
+    cache: Rc<RefCell<Option<Object>>>,
+}
+impl Callable for Thunk {
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn call(&self, _: &[Object], interpreter: &mut Interpreter) -> Result<Object> {
+        if let Some(value) = self.cache.borrow().as_ref() {
+            return Ok(value.clone());
+        }
+
+        let value = self.function.call(&[], interpreter)?;
+        *self.cache.borrow_mut() = Some(value.clone());
+        Ok(value)
+    }
+}
+
+// `compose(f, g)` is `fun (x) { return f(g(x)); }`, for any number of arguments `g` takes
+#[derive(Clone, Debug)]
+struct ComposedFn {
+    outer: Box<dyn Callable>,
+    inner: Box<dyn Callable>,
+}
+impl Callable for ComposedFn {
+    fn arity(&self) -> usize {
+        self.inner.arity()
+    }
+
+    fn call(&self, arguments: &[Object], interpreter: &mut Interpreter) -> Result<Object> {
+        let value = self.inner.call(arguments, interpreter)?;
+        self.outer.call(&[value], interpreter)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ForceFunction {}
+impl Callable for ForceFunction {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(&self, arguments: &[Object], interpreter: &mut Interpreter) -> Result<Object> {
+        match &arguments[0] {
+            Object::Call(thunk) if thunk.arity() == 0 => thunk.call(&[], interpreter),
+            _ => Err(native_error("force", "Expected argument to be a thunk")),
+        }
+    }
+}
+
+// Converts like `print` does, so instances use their `toString` method
+#[derive(Clone, Debug)]
+struct StrFunction {}
+impl Callable for StrFunction {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(&self, arguments: &[Object], interpreter: &mut Interpreter) -> Result<Object> {
+        let value = interpreter.stringify(&arguments[0])?;
+        interpreter.native_string_result("str", value)
+    }
+}
+
+// Prints like `print` does, but without the terminator, so output can be built up in pieces
+#[derive(Clone, Debug)]
+struct WriteFunction {}
+impl Callable for WriteFunction {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(&self, arguments: &[Object], interpreter: &mut Interpreter) -> Result<Object> {
+        let value = interpreter.stringify(&arguments[0])?;
+        let mut stdout = io::stdout();
+        // without a newline stdout isn't flushed on its own
+        write!(stdout, "{}", value)
+            .and_then(|_| stdout.flush())
+            .map_err(|err| native_error("write", &err.to_string()))?;
+        Ok(Object::Nil)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct PadFunction {
+    name: &'static str,
+    left: bool,
+}
+impl Callable for PadFunction {
+    fn arity(&self) -> usize {
+        3
+    }
+
+    fn call(&self, arguments: &[Object], interpreter: &mut Interpreter) -> Result<Object> {
+        let value = match &arguments[0] {
+            Object::String(value) => value,
+            _ => return Err(native_error(self.name, "Expected argument to be a string")),
+        };
+        let width = whole_number_argument(self.name, &arguments[1])?;
+        if width < 0 {
+            return Err(native_error(self.name, "Expected width to be non-negative"));
+        }
+        let fill = match &arguments[2] {
+            Object::Nil => ' ',
+            Object::String(fill) if fill.chars().count() == 1 => fill.chars().next().unwrap(),
+            _ => {
+                return Err(native_error(
+                    self.name,
+                    "Expected fill to be a single character",
+                ))
+            }
+        };
+
+        let length = value.chars().count();
+        let token = Token::new(TokenType::Identifier, self.name.to_string(), 0);
+        interpreter.check_string_length(&token, length.max(width as usize))?;
+
+        let padding: String =
+            std::iter::repeat_n(fill, (width as usize).saturating_sub(length)).collect();
+        let padded = if self.left {
+            padding + value
+        } else {
+            value.to_string() + &padding
+        };
+        Ok(Object::String(padded))
+    }
+}
+
+// `times(3, f)` calls `f(0)`, `f(1)` and `f(2)`
+#[derive(Clone, Debug)]
+struct TimesFunction {}
+impl Callable for TimesFunction {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(&self, arguments: &[Object], interpreter: &mut Interpreter) -> Result<Object> {
+        let count = whole_number_argument("times", &arguments[0])?;
+        if count < 0 {
+            return Err(native_error("times", "Expected count to be non-negative"));
+        }
+        let function = match &arguments[1] {
+            Object::Call(function) if function.arity() == 1 => function,
+            _ => {
+                return Err(native_error(
+                    "times",
+                    "Expected a function taking one argument",
+                ))
+            }
+        };
+
+        for index in 0..count {
+            function.call(&[Object::Number(index as f64)], interpreter)?;
+        }
+        Ok(Object::Nil)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct PowModFunction {}
+impl Callable for PowModFunction {
+    fn arity(&self) -> usize {
+        3
+    }
+
+    fn call(&self, arguments: &[Object], _: &mut Interpreter) -> Result<Object> {
+        let mut values = [0i64; 3];
+        for (value, argument) in values.iter_mut().zip(arguments) {
+            *value = match argument {
+                Object::Number(x) if x.fract() == 0.0 && *x >= 0.0 && *x <= i64::MAX as f64 => {
+                    *x as i64
+                }
+                _ => {
+                    return Err(native_error(
+                        "pow_mod",
+                        "Expected arguments to be non-negative whole numbers",
+                    ))
+                }
+            };
+        }
+        let [base, mut exponent, modulus] = values;
+        if modulus == 0 {
+            return Err(native_error("pow_mod", "Modulus must not be zero"));
+        }
+
+        // square and multiply, widening so the products can't overflow
+        let modulus = modulus as i128;
+        let mut base = base as i128 % modulus;
+        let mut result = 1 % modulus;
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result * base % modulus;
+            }
+            base = base * base % modulus;
+            exponent >>= 1;
+        }
+
+        Ok(Object::Number(result as f64))
+    }
+}
+
+fn map_key_error(token: &Token) -> LoxError {
+    LoxError::RuntimeError(token.clone(), "Map keys must be strings".to_string())
+}
+
+fn native_error(name: &str, message: &str) -> LoxError {
+    LoxError::RuntimeError(
+        Token::new(TokenType::Identifier, name.to_string(), 0),
+        format!("{}: {}", name, message),
+    )
 }
 
 #[derive(Clone, Debug)]
@@ -487,6 +1739,7 @@ pub struct UserFunction {
     body: Vec<Stmt>,
     closure: Rc<RefCell<Environment>>,
     is_initializer: bool,
+    doc: Option<String>,
 }
 impl UserFunction {
     pub fn new(
@@ -500,26 +1753,40 @@ impl UserFunction {
             body,
             closure: environment,
             is_initializer,
+            doc: None,
         }
     }
-    pub fn bind(&self, instance: Rc<RefCell<LoxInstance>>) -> UserFunction {
+
+    pub fn with_doc(mut self, doc: Option<String>) -> Self {
+        self.doc = doc;
+        self
+    }
+}
+impl Callable for UserFunction {
+    fn arity(&self) -> usize {
+        self.params.len()
+    }
+
+    fn bind(&self, instance: Rc<RefCell<LoxInstance>>) -> Box<dyn Callable> {
         let mut enviroment = Environment::new_with_enclosing(Rc::clone(&self.closure));
         enviroment.define(
             "this".to_string(),
             Some(Object::ClassInstance(Rc::clone(&instance))),
         );
         let enviroment = Rc::new(RefCell::new(enviroment));
-        UserFunction::new(
-            self.params.clone(),
-            self.body.clone(),
-            enviroment,
-            self.is_initializer,
+        Box::new(
+            UserFunction::new(
+                self.params.clone(),
+                self.body.clone(),
+                enviroment,
+                self.is_initializer,
+            )
+            .with_doc(self.doc.clone()),
         )
     }
-}
-impl Callable for UserFunction {
-    fn arity(&self) -> usize {
-        self.params.len()
+
+    fn doc(&self) -> Option<&str> {
+        self.doc.as_deref()
     }
 
     fn call(&self, arguments: &[Object], interpreter: &mut Interpreter) -> Result<Object> {
@@ -535,14 +1802,11 @@ impl Callable for UserFunction {
         let result = interpreter.execute_block(&self.body, environment);
 
         match result {
-            Ok(()) => Ok(Object::Nil),
-            Err(LoxError::Return(value)) => {
-                if self.is_initializer {
-                    self.closure.borrow().get_at(&this_token(), 0)
-                } else {
-                    Ok(value)
-                }
+            Ok(()) | Err(LoxError::Return(_)) if self.is_initializer => {
+                self.closure.borrow().get_at(&this_token(), 0)
             }
+            Ok(()) => Ok(Object::Nil),
+            Err(LoxError::Return(value)) => Ok(value),
             Err(x) => Err(x),
         }
     }
@@ -550,3 +1814,1460 @@ impl Callable for UserFunction {
 fn this_token() -> Token {
     Token::new(TokenType::This, "this".to_string(), 0)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolver::Resolver;
+
+    fn run_program(source: &str) -> Interpreter {
+        run_program_with(Interpreter::new(), source)
+    }
+
+    fn run_program_with(mut interpreter: Interpreter, source: &str) -> Interpreter {
+        let stmts = lox::run(source.to_string());
+        let depth_map = Resolver::new().run(&stmts).unwrap();
+        interpreter.add_expr_ids_depth(depth_map);
+        interpreter.interpret(&stmts);
+        interpreter
+    }
+
+    fn get_variable(interpreter: &Interpreter, name: &str) -> Object {
+        let token = Token::new(TokenType::Identifier, name.to_string(), 0);
+        interpreter.environment().borrow().get(&token).unwrap()
+    }
+
+    fn call_native(callable: &dyn Callable, arguments: &[Object]) -> Result<Object> {
+        callable.call(arguments, &mut Interpreter::new())
+    }
+
+    #[test]
+    fn commafy_large_integer() {
+        let result = call_native(&CommafyFunction {}, &[Object::Number(1234567.0)]);
+        assert_eq!(result, Ok(Object::String("1,234,567".into())));
+    }
+
+    #[test]
+    fn commafy_negative_number() {
+        let result = call_native(&CommafyFunction {}, &[Object::Number(-987654.0)]);
+        assert_eq!(result, Ok(Object::String("-987,654".into())));
+    }
+
+    #[test]
+    fn commafy_number_with_decimals() {
+        let result = call_native(&CommafyFunction {}, &[Object::Number(12345.678)]);
+        assert_eq!(result, Ok(Object::String("12,345.678".into())));
+    }
+
+    #[test]
+    fn commafy_non_number() {
+        let result = call_native(&CommafyFunction {}, &[Object::String("12".into())]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn loop_else_skipped_after_break() {
+        let interpreter = run_program(
+            r#"
+            var ranElse = false;
+            var i = 0;
+            while (i < 10) {
+                if (i == 3) break;
+                i = i + 1;
+            } else {
+                ranElse = true;
+            }
+            print ranElse;
+            "#,
+        );
+
+        assert_eq!(
+            get_variable(&interpreter, "ranElse"),
+            Object::Boolean(false)
+        );
+        assert_eq!(get_variable(&interpreter, "i"), Object::Number(3.0));
+    }
+
+    #[test]
+    fn loop_else_runs_when_loop_completes() {
+        let interpreter = run_program(
+            r#"
+            var ranElse = false;
+            for (var i = 0; i < 3; i = i + 1) {
+                print i;
+            } else {
+                ranElse = true;
+            }
+            print ranElse;
+            "#,
+        );
+
+        assert_eq!(get_variable(&interpreter, "ranElse"), Object::Boolean(true));
+    }
+
+    #[test]
+    fn else_after_unbraced_loop_belongs_to_if() {
+        let mut interpreter = Interpreter::new();
+        let source = r#"
+            var x = false;
+            var ran = "none";
+            if (true) while (x) ran = "loop"; else ran = "else";
+            ran
+            "#;
+        assert_eq!(interpreter.eval(source), Ok(Object::String("none".into())));
+
+        let source = r#"
+            var ran = "none";
+            if (false) for (;false;) ran = "loop"; else ran = "else";
+            ran
+            "#;
+        assert_eq!(interpreter.eval(source), Ok(Object::String("else".into())));
+    }
+
+    #[test]
+    fn call_class_method() {
+        let interpreter = run_program(
+            r#"
+            class Greeter {
+                greet(name) {
+                    return "Hello " + name;
+                }
+            }
+            var greeting = Greeter().greet("Lox");
+            print greeting;
+            "#,
+        );
+
+        assert_eq!(
+            get_variable(&interpreter, "greeting"),
+            Object::String("Hello Lox".into())
+        );
+    }
+
+    #[test]
+    fn method_reads_this_field() {
+        let interpreter = run_program(
+            r#"
+            class Counter {
+                current() {
+                    return this.count;
+                }
+            }
+            var counter = Counter();
+            counter.count = 7;
+            var count = counter.current();
+            print count;
+            "#,
+        );
+
+        assert_eq!(get_variable(&interpreter, "count"), Object::Number(7.0));
+    }
+
+    #[test]
+    fn call_result_of_conditional() {
+        let interpreter = run_program(
+            r#"
+            fun add(a, b) { return a + b; }
+            fun sub(a, b) { return a - b; }
+            var subtract = true;
+            var result = (subtract ? sub : add)(5, 3);
+            print result;
+            "#,
+        );
+
+        assert_eq!(get_variable(&interpreter, "result"), Object::Number(2.0));
+    }
+
+    #[test]
+    fn class_initializer_returns_instance() {
+        let interpreter = run_program(
+            r#"
+            class Point {
+                init(x) {
+                    this.x = x;
+                    if (x > 0) return;
+                    this.x = 0;
+                }
+            }
+            var point = Point(3);
+            var x = point.x;
+            var reinitialized = point.init(-1).x;
+            print x;
+            print reinitialized;
+            "#,
+        );
+
+        assert_eq!(get_variable(&interpreter, "x"), Object::Number(3.0));
+        assert_eq!(
+            get_variable(&interpreter, "reinitialized"),
+            Object::Number(0.0)
+        );
+    }
+
+    #[test]
+    fn inherit_superclass_method() {
+        let interpreter = run_program(
+            r#"
+            class A {
+                name() { return "A"; }
+            }
+            class B < A {}
+            var name = B().name();
+            print name;
+            "#,
+        );
+
+        assert_eq!(
+            get_variable(&interpreter, "name"),
+            Object::String("A".into())
+        );
+    }
+
+    #[test]
+    fn call_superclass_method() {
+        let interpreter = run_program(
+            r#"
+            class A {
+                name() { return "A"; }
+            }
+            class B < A {
+                name() { return "B extends " + super.name(); }
+            }
+            var name = B().name();
+            print name;
+            "#,
+        );
+
+        assert_eq!(
+            get_variable(&interpreter, "name"),
+            Object::String("B extends A".into())
+        );
+    }
+
+    #[test]
+    fn instance_truthiness_from_method() {
+        let interpreter = run_program(
+            r#"
+            class Empty {
+                is_truthy() { return false; }
+            }
+            var branch;
+            if (Empty()) branch = "then"; else branch = "else";
+            print branch;
+            "#,
+        );
+
+        assert_eq!(
+            get_variable(&interpreter, "branch"),
+            Object::String("else".into())
+        );
+    }
+
+    #[test]
+    fn break_out_of_while() {
+        let interpreter = run_program(
+            r#"
+            var i = 0;
+            while (true) {
+                i = i + 1;
+                if (i == 5) break;
+            }
+            print i;
+            "#,
+        );
+
+        assert_eq!(get_variable(&interpreter, "i"), Object::Number(5.0));
+    }
+
+    #[test]
+    fn break_out_of_for() {
+        let interpreter = run_program(
+            r#"
+            var last;
+            for (var i = 0; i < 10; i = i + 1) {
+                if (i == 3) break;
+                last = i;
+            }
+            print last;
+            "#,
+        );
+
+        assert_eq!(get_variable(&interpreter, "last"), Object::Number(2.0));
+    }
+
+    #[test]
+    fn continue_in_while() {
+        let interpreter = run_program(
+            r#"
+            var i = 0;
+            var sum = 0;
+            while (i < 5) {
+                i = i + 1;
+                if (i == 2) continue;
+                sum = sum + i;
+            }
+            print sum;
+            "#,
+        );
+
+        assert_eq!(get_variable(&interpreter, "sum"), Object::Number(13.0));
+    }
+
+    #[test]
+    fn continue_in_for_runs_increment() {
+        let interpreter = run_program(
+            r#"
+            var sum = 0;
+            for (var i = 0; i < 5; i = i + 1) {
+                if (i == 2) continue;
+                sum = sum + i;
+            }
+            print sum;
+            "#,
+        );
+
+        assert_eq!(get_variable(&interpreter, "sum"), Object::Number(8.0));
+    }
+
+    #[test]
+    fn exit_returns_code_to_host() {
+        let stmts = lox::run("var reached = false; exit(3); reached = true; print reached;".into());
+        let depth_map = Resolver::new().run(&stmts).unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.add_expr_ids_depth(depth_map);
+
+        assert_eq!(interpreter.interpret(&stmts), Some(3));
+        assert_eq!(
+            get_variable(&interpreter, "reached"),
+            Object::Boolean(false)
+        );
+    }
+
+    #[test]
+    fn exit_with_invalid_code() {
+        let result = call_native(&ExitFunction {}, &[Object::Number(1.5)]);
+        assert!(matches!(result, Err(LoxError::RuntimeError(..))));
+
+        let result = call_native(&ExitFunction {}, &[Object::Number(256.0)]);
+        assert!(matches!(result, Err(LoxError::RuntimeError(..))));
+    }
+
+    #[test]
+    fn call_lambda_from_variable() {
+        let interpreter = run_program(
+            r#"
+            var add = fun (a, b) { return a + b; };
+            var sum = add(1, 2);
+            print sum;
+            "#,
+        );
+
+        assert_eq!(get_variable(&interpreter, "sum"), Object::Number(3.0));
+    }
+
+    #[test]
+    fn pass_lambda_as_argument() {
+        let interpreter = run_program(
+            r#"
+            fun twice(f, x) { return f(f(x)); }
+            var result = twice(fun (x) { return x * 3; }, 2);
+            print result;
+            "#,
+        );
+
+        assert_eq!(get_variable(&interpreter, "result"), Object::Number(18.0));
+    }
+
+    #[test]
+    fn equals_ignore_case() {
+        let interpreter = run_program(
+            r#"
+            var ignoringCase = equals_ignore_case("ABC", "abc");
+            var strict = "ABC" == "abc";
+            print ignoringCase;
+            print strict;
+            "#,
+        );
+
+        assert_eq!(
+            get_variable(&interpreter, "ignoringCase"),
+            Object::Boolean(true)
+        );
+        assert_eq!(get_variable(&interpreter, "strict"), Object::Boolean(false));
+    }
+
+    #[test]
+    fn equals_ignore_case_non_strings() {
+        let result = call_native(
+            &EqualsIgnoreCaseFunction {},
+            &[Object::String("1".into()), Object::Number(1.0)],
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn string_repetition() {
+        let interpreter = run_program("var s = \"ab\" * 3; print s;");
+        assert_eq!(
+            get_variable(&interpreter, "s"),
+            Object::String("ababab".into())
+        );
+    }
+
+    #[test]
+    fn string_repetition_over_limit_errors() {
+        let interpreter = Interpreter::new().with_max_string_length(100);
+        let interpreter = run_program_with(interpreter, "var s = \"a\" * 1000000000; print s;");
+        let token = Token::new(TokenType::Identifier, "s".to_string(), 0);
+        assert!(interpreter.environment().borrow().get(&token).is_err());
+    }
+
+    #[test]
+    fn string_repetition_without_limit() {
+        let result = Interpreter::new().eval("\"a\" * 1000");
+        assert!(matches!(result, Ok(Object::String(s)) if s.len() == 1000));
+        assert!(Interpreter::new()
+            .with_max_string_length(999)
+            .eval("\"a\" * 1000")
+            .is_err());
+    }
+
+    #[test]
+    fn string_repetition_huge_count_errors() {
+        let mut interpreter = Interpreter::new();
+        assert!(interpreter.eval("\"ab\" * 1e19").is_err());
+        assert!(interpreter.eval("\"a\" * 1e19").is_err());
+        assert!(interpreter.eval("\"a\" * 1e300").is_err());
+    }
+
+    #[test]
+    fn string_limit_counts_characters() {
+        let mut interpreter = Interpreter::new().with_max_string_length(3);
+        assert_eq!(
+            interpreter.eval("\"é\" * 3"),
+            Ok(Object::String("ééé".into()))
+        );
+    }
+
+    #[test]
+    fn string_concatenation_over_limit_errors() {
+        let interpreter = Interpreter::new().with_max_string_length(4);
+        let interpreter = run_program_with(interpreter, "var s = \"abc\" + \"def\"; print s;");
+        let token = Token::new(TokenType::Identifier, "s".to_string(), 0);
+        assert!(interpreter.environment().borrow().get(&token).is_err());
+    }
+
+    #[test]
+    fn string_method_call() {
+        let interpreter = run_program("var s = \"hi\".upper(); print s;");
+        assert_eq!(get_variable(&interpreter, "s"), Object::String("HI".into()));
+    }
+
+    #[test]
+    fn number_method_call() {
+        let interpreter = run_program("var n = (3.7).floor(); print n;");
+        assert_eq!(get_variable(&interpreter, "n"), Object::Number(3.0));
+    }
+
+    #[test]
+    fn unknown_primitive_method_errors() {
+        let token = Token::new(TokenType::Identifier, "shout".to_string(), 1);
+        let result = PrimitiveMethod::get(&Object::String("hi".into()), &token);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn pow_mod_small_numbers() {
+        let arguments = [
+            Object::Number(2.0),
+            Object::Number(10.0),
+            Object::Number(1000.0),
+        ];
+        let result = call_native(&PowModFunction {}, &arguments);
+        assert_eq!(result, Ok(Object::Number(24.0)));
+    }
+
+    #[test]
+    fn pow_mod_zero_modulus() {
+        let arguments = [
+            Object::Number(2.0),
+            Object::Number(10.0),
+            Object::Number(0.0),
+        ];
+        let result = call_native(&PowModFunction {}, &arguments);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn eval_expression() {
+        assert_eq!(Interpreter::new().eval("1 + 2"), Ok(Object::Number(3.0)));
+    }
+
+    #[test]
+    fn eval_program_returns_last_expression() {
+        let result =
+            Interpreter::new().eval("var a = 20; fun double(x) { return x * 2; } double(a) + 2");
+        assert_eq!(result, Ok(Object::Number(42.0)));
+    }
+
+    #[test]
+    fn eval_without_final_expression() {
+        assert_eq!(Interpreter::new().eval("print 1;"), Ok(Object::Nil));
+    }
+
+    #[test]
+    fn eval_runtime_error() {
+        assert!(Interpreter::new().eval("-\"a\"").is_err());
+    }
+
+    #[test]
+    fn call_registered_native() {
+        let mut interpreter = Interpreter::new();
+        interpreter.register_native("double", 1, |arguments| match arguments[0] {
+            Object::Number(x) => Ok(Object::Number(x * 2.0)),
+            _ => Err(native_error("double", "Expected argument to be a number")),
+        });
+        let interpreter = run_program_with(interpreter, "var r = double(21); print r;");
+
+        assert_eq!(get_variable(&interpreter, "r"), Object::Number(42.0));
+    }
+
+    #[test]
+    fn math_sqrt() {
+        let interpreter = run_program("var r = sqrt(9); print r;");
+        assert_eq!(get_variable(&interpreter, "r"), Object::Number(3.0));
+    }
+
+    #[test]
+    fn math_floor() {
+        let interpreter = run_program("var r = floor(3.7); print r;");
+        assert_eq!(get_variable(&interpreter, "r"), Object::Number(3.0));
+    }
+
+    #[test]
+    fn math_pow() {
+        let interpreter = run_program("var r = pow(2, 10); print r;");
+        assert_eq!(get_variable(&interpreter, "r"), Object::Number(1024.0));
+    }
+
+    #[test]
+    fn math_type_error() {
+        let result = Interpreter::new().eval("sqrt(\"nine\")");
+        match result {
+            Err(LoxError::RuntimeError(token, message)) => {
+                assert_eq!(token.lexeme, "sqrt");
+                assert_eq!(message, "sqrt: Expected argument to be a number");
+            }
+            x => panic!("expected runtime error, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn eval_in_with_bindings() {
+        let mut interpreter = Interpreter::new();
+        let bindings = HashMap::from([
+            ("x".to_string(), Object::Number(3.0)),
+            ("y".to_string(), Object::Number(4.0)),
+        ]);
+
+        let result = interpreter.eval_in("var sum = x + y; sum", &bindings);
+        assert_eq!(result, Ok(Object::Number(7.0)));
+
+        for name in ["x", "y", "sum"] {
+            let token = Token::new(TokenType::Identifier, name.to_string(), 0);
+            assert!(interpreter.environment().borrow().get(&token).is_err());
+            assert!(interpreter.global_environment.borrow().get(&token).is_err());
+        }
+    }
+
+    #[test]
+    fn len_of_ascii_string() {
+        assert_eq!(
+            Interpreter::new().eval("len(\"hello\")"),
+            Ok(Object::Number(5.0))
+        );
+    }
+
+    #[test]
+    fn len_counts_characters_not_bytes() {
+        assert_eq!(
+            Interpreter::new().eval("len(\"héllo🦀\")"),
+            Ok(Object::Number(6.0))
+        );
+    }
+
+    #[test]
+    fn len_type_error() {
+        assert!(Interpreter::new().eval("len(5)").is_err());
+    }
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let source = "seed(42); random() + random_int(0, 1000000) * 10";
+        let first = Interpreter::new().eval(source);
+        let second = Interpreter::new().eval(source);
+
+        assert!(first.is_ok());
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn random_in_unit_interval() {
+        let mut interpreter = Interpreter::new();
+        for _ in 0..100 {
+            match interpreter.eval("random()") {
+                Ok(Object::Number(x)) => assert!((0.0..1.0).contains(&x)),
+                x => panic!("expected number, found {:?}", x),
+            }
+        }
+    }
+
+    #[test]
+    fn random_int_respects_bounds() {
+        let mut interpreter = Interpreter::new();
+        for _ in 0..100 {
+            match interpreter.eval("random_int(-2, 2)") {
+                Ok(Object::Number(x)) => assert!((-2.0..=2.0).contains(&x) && x.fract() == 0.0),
+                x => panic!("expected number, found {:?}", x),
+            }
+        }
+        assert_eq!(
+            interpreter.eval("random_int(7, 7)"),
+            Ok(Object::Number(7.0))
+        );
+    }
+
+    #[test]
+    fn random_int_invalid_bounds() {
+        assert!(Interpreter::new().eval("random_int(3, 1)").is_err());
+        assert!(Interpreter::new().eval("random_int(0.5, 1)").is_err());
+    }
+
+    #[test]
+    fn clamp_bounds_value() {
+        let mut interpreter = Interpreter::new();
+        assert_eq!(interpreter.eval("clamp(5, 0, 3)"), Ok(Object::Number(3.0)));
+        assert_eq!(interpreter.eval("clamp(-1, 0, 3)"), Ok(Object::Number(0.0)));
+        assert_eq!(interpreter.eval("clamp(2, 0, 3)"), Ok(Object::Number(2.0)));
+    }
+
+    #[test]
+    fn clamp_invalid_bounds() {
+        assert!(Interpreter::new().eval("clamp(1, 3, 0)").is_err());
+    }
+
+    #[test]
+    fn lerp_midpoint() {
+        assert_eq!(
+            Interpreter::new().eval("lerp(0, 10, 0.5)"),
+            Ok(Object::Number(5.0))
+        );
+    }
+
+    #[test]
+    fn map_literal_and_read() {
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval("var m = {\"a\": 1, \"b\": 2}; m[\"b\"]");
+        assert_eq!(result, Ok(Object::Number(2.0)));
+    }
+
+    #[test]
+    fn map_display() {
+        let result = Interpreter::new().eval("var m = {\"b\": 2, \"a\": \"x\"}; m");
+        assert_eq!(result.unwrap().to_string(), "{a: x, b: 2}");
+    }
+
+    #[test]
+    fn map_display_containing_itself() {
+        let source = "var m = {\"a\": 1}; m[\"self\"] = m; m[\"inner\"] = {\"m\": m}; m";
+        let result = Interpreter::new().eval(source);
+        assert_eq!(
+            result.unwrap().to_string(),
+            "{a: 1, inner: {m: {...}}, self: {...}}"
+        );
+    }
+
+    #[test]
+    fn map_overwrite() {
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval("var m = {\"a\": 1}; m[\"a\"] = 3; m[\"a\"]");
+        assert_eq!(result, Ok(Object::Number(3.0)));
+    }
+
+    #[test]
+    fn map_missing_key_is_nil() {
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval("var m = {}; m[\"missing\"]");
+        assert_eq!(result, Ok(Object::Nil));
+    }
+
+    #[test]
+    fn map_non_string_key_errors() {
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval("var m = {\"a\": 1}; m[1]");
+        match result {
+            Err(LoxError::RuntimeError(_, message)) => {
+                assert_eq!(message, "Map keys must be strings")
+            }
+            x => panic!("expected runtime error, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn break_inside_function_loop_returns_normally() {
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval(
+            r#"
+            fun first_over(limit) {
+                var i = 0;
+                while (true) {
+                    i = i + 1;
+                    if (i > limit) break;
+                }
+                return i;
+            }
+            first_over(3)
+            "#,
+        );
+
+        assert_eq!(result, Ok(Object::Number(4.0)));
+    }
+
+    #[test]
+    fn function_without_return_after_break_returns_nil() {
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval("fun f() { while (true) { break; } } f()");
+
+        assert_eq!(result, Ok(Object::Nil));
+    }
+
+    #[test]
+    fn super_reads_field_set_by_superclass_init() {
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval(
+            r#"
+            class Base {
+                init() {
+                    this.x = 10;
+                }
+            }
+            class Derived < Base {
+                read() {
+                    return super.x;
+                }
+            }
+            Derived().read()
+            "#,
+        );
+
+        assert_eq!(result, Ok(Object::Number(10.0)));
+    }
+
+    #[test]
+    fn super_undefined_property() {
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval(
+            "class Base {} class Derived < Base { read() { return super.missing; } } Derived().read()",
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn lazy_thunk_runs_once() {
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval(
+            r#"
+            var runs = 0;
+            var value = lazy(fun () {
+                runs = runs + 1;
+                return 42;
+            });
+            var sum = force(value) + force(value) + value();
+            runs * 1000 + sum
+            "#,
+        );
+
+        assert_eq!(result, Ok(Object::Number(1126.0)));
+    }
+
+    #[test]
+    fn lazy_requires_function_without_parameters() {
+        assert!(Interpreter::new().eval("lazy(1)").is_err());
+        assert!(Interpreter::new()
+            .eval("lazy(fun (x) { return x; })")
+            .is_err());
+    }
+
+    #[test]
+    fn bind_defines_this_for_user_methods_only() {
+        let mut interpreter = Interpreter::new();
+        let point = interpreter
+            .eval("class Point { init() { this.x = 3; } get_x() { return this.x; } } Point()");
+        let instance = match point {
+            Ok(Object::ClassInstance(instance)) => instance,
+            x => panic!("expected an instance, found {:?}", x),
+        };
+
+        let method = instance.borrow().class().find_method("get_x").unwrap();
+        let bound = method.bind(Rc::clone(&instance));
+        assert_eq!(bound.call(&[], &mut interpreter), Ok(Object::Number(3.0)));
+
+        let native = CommafyFunction {}.bind(instance);
+        let result = native.call(&[Object::Number(1000.0)], &mut interpreter);
+        assert_eq!(result, Ok(Object::String("1,000".into())));
+    }
+
+    #[test]
+    fn hints_about_method_without_call() {
+        let source = "class A { m() { return 1; } } var a = A(); a.m; a.m();";
+        let mut interpreter = run_program_with(Interpreter::new().with_call_hints(true), source);
+
+        assert_eq!(
+            interpreter.take_hints(),
+            vec!["[line 1] Hint: 'm' is a method and wasn't called, did you mean 'm()'?"]
+        );
+        assert!(interpreter.take_hints().is_empty());
+    }
+
+    #[test]
+    fn no_hint_about_callable_field() {
+        let source = "class A { m() {} } var a = A(); a.f = clock; a.f; a.m = clock; a.m;";
+        let mut interpreter = run_program_with(Interpreter::new().with_call_hints(true), source);
+
+        assert!(interpreter.take_hints().is_empty());
+    }
+
+    #[test]
+    fn parse_int_with_radix() {
+        let mut interpreter = Interpreter::new();
+        assert_eq!(
+            interpreter.eval("parse_int(\"ff\", 16)"),
+            Ok(Object::Number(255.0))
+        );
+        assert_eq!(
+            interpreter.eval("parse_int(\"101\", 2)"),
+            Ok(Object::Number(5.0))
+        );
+        assert_eq!(
+            interpreter.eval("parse_int(\"-z\", 36)"),
+            Ok(Object::Number(-35.0))
+        );
+    }
+
+    #[test]
+    fn parse_int_invalid_digit_is_nil() {
+        assert_eq!(
+            Interpreter::new().eval("parse_int(\"102\", 2)"),
+            Ok(Object::Nil)
+        );
+    }
+
+    #[test]
+    fn parse_int_radix_out_of_range() {
+        assert!(Interpreter::new().eval("parse_int(\"1\", 1)").is_err());
+        assert!(Interpreter::new().eval("parse_int(\"1\", 37)").is_err());
+    }
+
+    #[test]
+    fn num_and_str_conversions() {
+        let mut interpreter = Interpreter::new();
+        assert_eq!(interpreter.eval("num(\"3.5\")"), Ok(Object::Number(3.5)));
+        assert_eq!(
+            interpreter.eval("str(42)"),
+            Ok(Object::String("42".to_string()))
+        );
+        assert_eq!(
+            interpreter.eval("num(str(0.25)) == 0.25 and str(num(\"7\")) == \"7\""),
+            Ok(Object::Boolean(true))
+        );
+    }
+
+    #[test]
+    fn str_of_instance_uses_to_string_method() {
+        let mut interpreter = Interpreter::new();
+        assert_eq!(
+            interpreter.eval("class P { toString() { return \"point\"; } } str(P())"),
+            Ok(Object::String("point".into()))
+        );
+        assert_eq!(
+            interpreter.eval("class Q {} str(Q())"),
+            Ok(Object::String("Q instance".into()))
+        );
+    }
+
+    #[test]
+    fn num_of_unparseable_string_is_error() {
+        assert!(Interpreter::new().eval("num(\"abc\")").is_err());
+        assert!(Interpreter::new().eval("num(nil)").is_err());
+    }
+
+    #[test]
+    fn pad_strings() {
+        let mut interpreter = Interpreter::new();
+        assert_eq!(
+            interpreter.eval("pad_left(\"7\", 3, \"0\")"),
+            Ok(Object::String("007".to_string()))
+        );
+        assert_eq!(
+            interpreter.eval("pad_right(\"ab\", 4, nil)"),
+            Ok(Object::String("ab  ".to_string()))
+        );
+        assert!(interpreter.eval("pad_left(\"a\", 3, \"xy\")").is_err());
+        assert!(interpreter.eval("pad_left(\"a\", -1, nil)").is_err());
+    }
+
+    #[test]
+    fn pad_over_limit_errors() {
+        let mut interpreter = Interpreter::new().with_max_string_length(10);
+        assert!(interpreter.eval("pad_left(\"a\", 1e15, nil)").is_err());
+        assert_eq!(
+            interpreter.eval("pad_right(\"a\", 10, \"-\")"),
+            Ok(Object::String("a---------".to_string()))
+        );
+    }
+
+    #[test]
+    fn natives_building_strings_over_limit_error() {
+        let input = io::Cursor::new("short\nmuch too long");
+        let mut interpreter = Interpreter::new()
+            .with_max_string_length(6)
+            .with_input(input);
+
+        assert_eq!(
+            interpreter.eval("read_line()"),
+            Ok(Object::String("short".to_string()))
+        );
+        assert!(interpreter.eval("read_line()").is_err());
+        assert!(interpreter.eval("str(1234567)").is_err());
+        assert!(interpreter.eval("commafy(1234567)").is_err());
+        assert_eq!(
+            interpreter.eval("commafy(1234)"),
+            Ok(Object::String("1,234".to_string()))
+        );
+    }
+
+    #[test]
+    fn pad_wide_string_is_unchanged() {
+        assert_eq!(
+            Interpreter::new().eval("pad_left(\"abcd\", 2, \"*\")"),
+            Ok(Object::String("abcd".to_string()))
+        );
+    }
+
+    #[test]
+    fn write_is_a_native_and_not_a_keyword() {
+        let mut interpreter = Interpreter::new();
+        assert_eq!(interpreter.eval("write(\"\")"), Ok(Object::Nil));
+        assert_eq!(
+            interpreter.eval("var write = 1; write + 1"),
+            Ok(Object::Number(2.0))
+        );
+    }
+
+    #[test]
+    fn read_line_from_input() {
+        let input = io::Cursor::new("first line\nsecond");
+        let mut interpreter = Interpreter::new().with_input(input);
+
+        assert_eq!(
+            interpreter.eval("read_line()"),
+            Ok(Object::String("first line".to_string()))
+        );
+        assert_eq!(
+            interpreter.eval("read_line()"),
+            Ok(Object::String("second".to_string()))
+        );
+        assert_eq!(interpreter.eval("read_line()"), Ok(Object::Nil));
+    }
+
+    #[test]
+    fn injected_clock() {
+        let mut interpreter = Interpreter::new().with_clock(|| 42.0);
+
+        assert_eq!(interpreter.eval("clock()"), Ok(Object::Number(42.0)));
+        assert_eq!(
+            interpreter.eval("str(clock())"),
+            Ok(Object::String("42".to_string()))
+        );
+    }
+
+    // Test threads have a 2MB stack, so deep bodies are tested with a lower limit
+    fn call_depth_error(source: &str) -> String {
+        match Interpreter::new().with_max_call_depth(40).eval(source) {
+            Err(LoxError::RuntimeError(_, message)) => message,
+            x => panic!("expected runtime error, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn infinite_recursion_is_runtime_error() {
+        let mut interpreter = Interpreter::new();
+        let (token, message) = match interpreter.eval("fun f(n) {\n return f(n + 1);\n}\nf(0)") {
+            Err(LoxError::RuntimeError(token, message)) => (token, message),
+            x => panic!("expected runtime error, found {:?}", x),
+        };
+        assert_eq!(message, "Stack overflow: maximum call depth exceeded");
+        assert_eq!(token.line, 2);
+
+        let after = interpreter.eval("fun g(n) { return n; } g(1)");
+        assert_eq!(after, Ok(Object::Number(1.0)));
+    }
+
+    #[test]
+    fn deep_recursion_through_nested_blocks_is_runtime_error() {
+        let message = call_depth_error(
+            r#"
+            fun f(n) {
+                if (n > 0) {{{{{{
+                    var a = 1;
+                    while (true) { return ((((((f(n - 1) + 1)))))) * a; }
+                }}}}}}
+                return 0;
+            }
+            f(5000)
+            "#,
+        );
+        assert_eq!(message, "Stack overflow: maximum call depth exceeded");
+    }
+
+    #[test]
+    fn deep_recursion_through_constructors_is_runtime_error() {
+        let message =
+            call_depth_error("class A { init(n) { if (n > 0) { this.c = A(n - 1); } } } A(5000)");
+        assert_eq!(message, "Stack overflow: maximum call depth exceeded");
+    }
+
+    #[test]
+    fn deep_recursion_through_to_string_is_runtime_error() {
+        let message =
+            call_depth_error("class A { toString() { return \"a\" + this; } } \"\" + A()");
+        assert_eq!(message, "Stack overflow: maximum call depth exceeded");
+    }
+
+    #[test]
+    fn native_errors_are_reported_at_the_call() {
+        match Interpreter::new().eval("\n\ncommafy(\"a\")") {
+            Err(LoxError::RuntimeError(token, message)) => {
+                assert_eq!(token.line, 3);
+                assert_eq!(message, "commafy: Expected argument to be a number");
+            }
+            x => panic!("expected runtime error, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn call_depth_limit_is_configurable() {
+        let source = "fun down(n) { if (n == 0) return 0; return down(n - 1); } down(10)";
+
+        assert!(Interpreter::new()
+            .with_max_call_depth(5)
+            .eval(source)
+            .is_err());
+        assert_eq!(
+            Interpreter::new().with_max_call_depth(11).eval(source),
+            Ok(Object::Number(0.0))
+        );
+    }
+
+    #[test]
+    fn compound_assignment_on_local() {
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval(
+            r#"
+            fun run() {
+                var x = 10;
+                x += 5;
+                x -= 3;
+                x *= 4;
+                x /= 8;
+                return x;
+            }
+            run()
+            "#,
+        );
+        assert_eq!(result, Ok(Object::Number(6.0)));
+
+        let result = interpreter.eval(
+            "class Counter { init() { this.count = 1; } } var c = Counter(); c.count += 2; c.count",
+        );
+        assert_eq!(result, Ok(Object::Number(3.0)));
+    }
+
+    #[test]
+    fn compound_assignment_evaluates_object_once() {
+        let result = Interpreter::new().eval(
+            r#"
+            class Box { init() { this.v = 1; } }
+            var box = Box();
+            var calls = 0;
+            fun get() { calls += 1; return box; }
+            get().v += 5;
+            calls * 10 + box.v
+            "#,
+        );
+        assert_eq!(result, Ok(Object::Number(16.0)));
+    }
+
+    #[test]
+    fn doc_of_functions() {
+        let interpreter = run_program(
+            r#"
+            /// Greets someone
+            fun greet(name) { return "hi " + name; }
+            fun plain() {}
+            class Robot {
+                /// Beeps
+                beep() {}
+            }
+            var greet_doc = doc(greet);
+            var plain_doc = doc(plain);
+            var beep_doc = doc(Robot().beep);
+            print greet_doc + beep_doc;
+            print plain_doc;
+            "#,
+        );
+
+        assert_eq!(
+            get_variable(&interpreter, "greet_doc"),
+            Object::String("Greets someone".to_string())
+        );
+        assert_eq!(get_variable(&interpreter, "plain_doc"), Object::Nil);
+        assert_eq!(
+            get_variable(&interpreter, "beep_doc"),
+            Object::String("Beeps".to_string())
+        );
+    }
+
+    #[test]
+    fn times_calls_with_each_index() {
+        let interpreter = run_program(
+            r#"
+            var calls = "";
+            fun recorder(index) { calls = calls + str(index); }
+            var result = times(3, recorder);
+            print result;
+            "#,
+        );
+
+        assert_eq!(
+            get_variable(&interpreter, "calls"),
+            Object::String("012".to_string())
+        );
+        assert_eq!(get_variable(&interpreter, "result"), Object::Nil);
+    }
+
+    #[test]
+    fn times_with_negative_count_is_error() {
+        assert!(Interpreter::new()
+            .eval("times(-1, fun (i) { print i; })")
+            .is_err());
+    }
+
+    fn switch_result(value: &str) -> Object {
+        let interpreter = run_program(&format!(
+            r#"
+            var result = "none";
+            switch ({}) {{
+                case 1:
+                    result = "one";
+                case "two":
+                    result = "two";
+                default:
+                    result = "other";
+            }}
+            print result;
+            "#,
+            value
+        ));
+        get_variable(&interpreter, "result")
+    }
+
+    #[test]
+    fn switch_runs_matching_case() {
+        assert_eq!(switch_result("1"), Object::String("one".to_string()));
+        assert_eq!(switch_result("\"two\""), Object::String("two".to_string()));
+    }
+
+    #[test]
+    fn switch_runs_default() {
+        assert_eq!(switch_result("nil"), Object::String("other".to_string()));
+    }
+
+    #[test]
+    fn break_in_switch_exits_enclosing_loop() {
+        let mut interpreter = Interpreter::new();
+        let source = r#"
+            var count = 0;
+            while (true) {
+                count = count + 1;
+                switch (count) {
+                    case 3: break;
+                }
+            }
+            count
+            "#;
+        assert_eq!(interpreter.eval(source), Ok(Object::Number(3.0)));
+    }
+
+    #[test]
+    fn case_and_default_are_names_outside_switch() {
+        let mut interpreter = Interpreter::new();
+        let source = "var case = 1; fun default(x) { return x + 1; } default(case)";
+        assert_eq!(interpreter.eval(source), Ok(Object::Number(2.0)));
+    }
+
+    #[test]
+    fn switch_without_match_or_default_does_nothing() {
+        let interpreter = run_program(
+            r#"
+            var result = "none";
+            switch (3) {
+                case 1: result = "one";
+            }
+            print result;
+            "#,
+        );
+        assert_eq!(
+            get_variable(&interpreter, "result"),
+            Object::String("none".to_string())
+        );
+    }
+
+    #[test]
+    fn print_separator_and_terminator() {
+        let mut interpreter = Interpreter::new();
+        let values = [
+            Object::Number(1.0),
+            Object::String("two".to_string()),
+            Object::Nil,
+        ];
+        assert_eq!(
+            interpreter.format_print(&values),
+            Ok("1 two nil\n".to_string())
+        );
+
+        interpreter.set_print_separator(", ");
+        interpreter.set_print_terminator(";\n");
+        assert_eq!(
+            interpreter.format_print(&values),
+            Ok("1, two, nil;\n".to_string())
+        );
+    }
+
+    #[test]
+    fn logic_xor() {
+        let mut interpreter = Interpreter::new();
+        assert_eq!(
+            interpreter.eval("(true xor false) == true"),
+            Ok(Object::Boolean(true))
+        );
+        assert_eq!(
+            interpreter.eval("(1 xor 2) == false"),
+            Ok(Object::Boolean(true))
+        );
+        assert_eq!(interpreter.eval("nil xor \"a\""), Ok(Object::Boolean(true)));
+        assert_eq!(
+            interpreter.eval("false xor nil"),
+            Ok(Object::Boolean(false))
+        );
+        // `nil xor (2 == 2)`, not `(nil xor 2) == 2`
+        assert_eq!(
+            interpreter.eval("nil xor 2 == 2"),
+            Ok(Object::Boolean(true))
+        );
+    }
+
+    fn loop_closure_result(captured: &str) -> Object {
+        let interpreter = run_program(&format!(
+            r#"
+            var first;
+            for (var i = 0; i < 3; i = i + 1) {{
+                var j = i;
+                fun closure() {{ return {}; }}
+                if (j == 0) first = closure;
+            }}
+            var result = first();
+            print result;
+            "#,
+            captured
+        ));
+        get_variable(&interpreter, "result")
+    }
+
+    #[test]
+    fn loop_closures_share_loop_variable() {
+        assert_eq!(loop_closure_result("i"), Object::Number(3.0));
+    }
+
+    #[test]
+    fn loop_closures_capture_fresh_body_binding() {
+        assert_eq!(loop_closure_result("j"), Object::Number(0.0));
+    }
+
+    #[test]
+    fn compose_functions() {
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval(
+            r#"
+            fun double(x) { return x * 2; }
+            fun inc(x) { return x + 1; }
+            compose(double, inc)(5)
+            "#,
+        );
+        assert_eq!(result, Ok(Object::Number(12.0)));
+
+        assert!(interpreter.eval("compose(1, sqrt)").is_err());
+        assert!(interpreter.eval("compose(pow, sqrt)").is_err());
+    }
+
+    #[test]
+    fn readonly_global() {
+        let mut interpreter = Interpreter::new();
+        interpreter.define_readonly("config", Object::Number(1.0));
+
+        assert_eq!(interpreter.eval("config + 1"), Ok(Object::Number(2.0)));
+        assert!(matches!(
+            interpreter.eval("config = 2;"),
+            Err(LoxError::ResolverError(..))
+        ));
+        assert!(matches!(
+            interpreter.eval("var config = 3; print config;"),
+            Err(LoxError::ResolverError(..))
+        ));
+        assert!(matches!(
+            interpreter.eval("fun f() { var config = 3; return config; } f()"),
+            Err(LoxError::ResolverError(..))
+        ));
+        assert_eq!(interpreter.eval("config"), Ok(Object::Number(1.0)));
+    }
+
+    #[test]
+    fn readonly_global_assignment_fails_at_runtime() {
+        let mut interpreter = Interpreter::new();
+        interpreter.define_readonly("config", Object::Number(1.0));
+        let token = Token::new(TokenType::Identifier, "config".to_string(), 1);
+
+        let result = interpreter
+            .global_environment
+            .borrow_mut()
+            .assign(&token, Object::Nil);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn instances_are_equal_by_identity() {
+        let mut interpreter = Interpreter::new();
+        let result =
+            interpreter.eval("class Foo {} var a = Foo(); var b = a; a == b and a != Foo()");
+
+        assert_eq!(result, Ok(Object::Boolean(true)));
+    }
+
+    #[test]
+    fn weak_ref_does_not_keep_instance_alive() {
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval(
+            r#"
+            class Foo {}
+            var foo = Foo();
+            var weak = weak_ref(foo);
+            var alive = deref(weak) == foo;
+            foo = nil;
+            alive and deref(weak) == nil
+            "#,
+        );
+
+        assert_eq!(result, Ok(Object::Boolean(true)));
+    }
+
+    #[test]
+    fn pipeline_calls_functions_in_order() {
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval(
+            r#"
+            fun double(x) { return x * 2; }
+            fun inc(x) { return x + 1; }
+            3 |> double |> inc
+            "#,
+        );
+
+        assert_eq!(result, Ok(Object::Number(7.0)));
+        assert!(Interpreter::new().eval("3 |> 4").is_err());
+    }
+
+    #[test]
+    fn instances_use_to_string_method() {
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval(
+            r#"
+            class Point {
+                init(x) { this.x = x; }
+                toString() { return "Point(" + this.x + ")"; }
+            }
+            "at " + Point(3)
+            "#,
+        );
+
+        assert_eq!(result, Ok(Object::String("at Point(3)".into())));
+    }
+
+    #[test]
+    fn instances_without_to_string_use_default_format() {
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval("class Foo {} Foo() + \"!\"");
+
+        assert_eq!(result, Ok(Object::String("Foo instance!".into())));
+    }
+
+    #[test]
+    fn hooks_taking_arguments_are_ignored() {
+        let mut interpreter = Interpreter::new();
+        assert_eq!(
+            interpreter.eval("class A { toString(x) { return x; } } A() + \"!\""),
+            Ok(Object::String("A instance!".into()))
+        );
+        assert_eq!(
+            interpreter.eval("class B { is_truthy(x) { return false; } } !B()"),
+            Ok(Object::Boolean(false))
+        );
+    }
+
+    #[test]
+    fn type_names() {
+        let cases = [
+            ("type(1)", "number"),
+            ("type(\"a\")", "string"),
+            ("type(true)", "boolean"),
+            ("type(nil)", "nil"),
+            ("type(clock)", "function"),
+            ("type(fun () {})", "function"),
+            ("class Foo {} type(Foo)", "class"),
+            ("class Foo {} type(Foo())", "instance"),
+            ("var m = {}; type(m)", "map"),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(
+                Interpreter::new().eval(source),
+                Ok(Object::String(expected.into())),
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn type_annotations_are_not_enforced() {
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval(
+            r#"
+            var x: Number = 1;
+            fun f(a: Number) -> Number { return a + x; }
+            var g = fun (b: String) -> String { return b; };
+            f(2) + g(3)
+            "#,
+        );
+
+        assert_eq!(result, Ok(Object::Number(6.0)));
+    }
+}
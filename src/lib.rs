@@ -0,0 +1,23 @@
+pub mod ast_printer;
+pub mod ast_stats;
+pub mod cst;
+mod environment;
+pub mod error;
+mod expr;
+pub mod interpreter;
+pub mod lox;
+pub mod lox_callable;
+pub mod lox_class;
+pub mod lox_instance;
+mod object;
+pub mod parser;
+mod primitive_method;
+pub mod resolver;
+pub mod scanner;
+mod stmt;
+pub mod token;
+pub mod token_type;
+pub mod transform;
+pub mod typechecker;
+
+pub use object::Object;
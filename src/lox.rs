@@ -2,11 +2,14 @@ use super::interpreter::Interpreter;
 use super::parser::ParseResult;
 use super::parser::Parser;
 use super::scanner::Scanner;
-use super::token::Token;
+use super::token::{position, Token};
 use super::token_type::TokenType;
+use crate::ast_printer::ASTPrinter;
+use crate::ast_stats::AstStats;
 use crate::error::LoxError;
 use crate::resolver::Resolver;
 use crate::stmt::Stmt;
+use crate::typechecker::TypeChecker;
 use std::error::Error;
 use std::fs::File;
 use std::io;
@@ -17,49 +20,168 @@ use std::sync::atomic::{AtomicBool, Ordering};
 static HAD_ERROR: AtomicBool = AtomicBool::new(false);
 static HAD_RUNTIME_ERROR: AtomicBool = AtomicBool::new(false);
 
-pub fn run_file(path: String) -> Result<(), Box<dyn Error>> {
+fn read_file(path: String) -> io::Result<String> {
     let mut f = File::open(path)?;
     let mut buffer = String::new();
     f.read_to_string(&mut buffer)?;
-    let stmts = run(buffer);
-    let depth_map = Resolver::new().run(&stmts).map_err(|err| {
-        println!("{}", err);
-        err
-    })?;
+    Ok(buffer)
+}
+
+// How a script run ended, mapped to the process exit code following jlox conventions
+#[derive(Debug, PartialEq)]
+pub enum RunStatus {
+    Success,
+    CompileError,
+    RuntimeError,
+    Exit(i32),
+}
+
+impl RunStatus {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            RunStatus::Success => 0,
+            // EX_DATAERR (65) The input data was incorrect in some way.
+            RunStatus::CompileError => 65,
+            // EX_SOFTWARE (70) An internal software error has been detected.
+            RunStatus::RuntimeError => 70,
+            RunStatus::Exit(code) => *code,
+        }
+    }
+}
+
+pub fn run_file(path: String, warnings_as_errors: bool) -> Result<RunStatus, Box<dyn Error>> {
+    Ok(run_source(read_file(path)?, warnings_as_errors))
+}
+
+// Runs a whole program, the same way for a script file as for one piped through stdin
+pub fn run_source(source: String, warnings_as_errors: bool) -> RunStatus {
+    let stmts = run(source);
+    if HAD_ERROR.load(Ordering::Relaxed) {
+        return RunStatus::CompileError;
+    }
+
     let mut interpreter = Interpreter::new();
+    let mut resolver = Resolver::new()
+        .with_condition_assignment_warning(true)
+        .with_warnings_as_errors(warnings_as_errors)
+        .with_strict_assignments(true)
+        .with_globals(interpreter.global_names())
+        .with_readonly(interpreter.readonly_names());
+    let depth_map = match resolver.run(&stmts) {
+        Ok(depth_map) => depth_map,
+        Err(err) => {
+            println!("{}", err);
+            return RunStatus::CompileError;
+        }
+    };
+    if let Err(err) = TypeChecker::new()
+        .with_strict(warnings_as_errors)
+        .check(&stmts)
+    {
+        println!("{}", err);
+        return RunStatus::CompileError;
+    }
     interpreter.add_expr_ids_depth(depth_map);
-    interpreter.interpret(&stmts);
+    if let Some(code) = interpreter.interpret(&stmts) {
+        return RunStatus::Exit(code);
+    }
+
+    if HAD_RUNTIME_ERROR.load(Ordering::Relaxed) {
+        RunStatus::RuntimeError
+    } else {
+        RunStatus::Success
+    }
+}
+
+pub fn run_tokens(path: String) -> Result<RunStatus, Box<dyn Error>> {
+    print!("{}", dump_tokens(read_file(path)?));
 
     if HAD_ERROR.load(Ordering::Relaxed) {
-        Err("Some error occured".into())
+        Ok(RunStatus::CompileError)
     } else {
-        Ok(())
+        Ok(RunStatus::Success)
+    }
+}
+
+// One scanned token per line, without parsing the source
+pub fn dump_tokens(source: String) -> String {
+    let mut scanner = Scanner::new(source);
+    scanner.scan_tokens();
+
+    scanner
+        .tokens
+        .iter()
+        .map(|token| format!("[line {}] {}\n", token.line, token))
+        .collect()
+}
+
+pub fn run_ast(path: String) -> Result<RunStatus, Box<dyn Error>> {
+    let stmts = run(read_file(path)?);
+    if HAD_ERROR.load(Ordering::Relaxed) {
+        return Ok(RunStatus::CompileError);
     }
+    print!("{}", ASTPrinter::print_program(&stmts));
+
+    Ok(RunStatus::Success)
+}
+
+pub fn run_ast_stats(path: String) -> Result<RunStatus, Box<dyn Error>> {
+    let stmts = run(read_file(path)?);
+    if HAD_ERROR.load(Ordering::Relaxed) {
+        return Ok(RunStatus::CompileError);
+    }
+    println!("{}", AstStats::new(&stmts));
+
+    Ok(RunStatus::Success)
 }
 
 pub fn run_prompt() {
-    let mut interpreter = Interpreter::new();
+    let mut interpreter = Interpreter::new().with_call_hints(true);
+    // kept across lines so names declared on earlier lines resolve to the same scope
+    let mut resolver = Resolver::new().with_unused_check(false);
+    // lines of a statement that is still unfinished, like a function missing its closing }
+    let mut pending = String::new();
     loop {
         let mut input = String::new();
-        print!("> ");
+        print!("{}", if pending.is_empty() { "> " } else { "... " });
         io::stdout().flush().unwrap(); // print! needs to flush so it appears on screen
         match io::stdin().read_line(&mut input) {
             Ok(_) => {
                 if input.len() <= 1 {
                     // if input has only \n
-                    break;
+                    if pending.is_empty() {
+                        break;
+                    }
+                    // a blank line cancels the unfinished statement
+                    pending.clear();
+                    continue;
                 }
-                let stmts = repl_interpret(input);
-                match stmts {
-                    ReplStatements::List(x) => {
-                        Resolver::new()
-                            .run(&x)
-                            .map(|map| interpreter.add_expr_ids_depth(map))
-                            .unwrap(); // TODO Add error treatment to prompt function
-                        interpreter.interpret(&x);
+                if let Some(source) = input
+                    .trim_end()
+                    .strip_prefix(":ast ")
+                    .filter(|_| pending.is_empty())
+                {
+                    match repl_ast(source) {
+                        Ok(tree) => println!("{}", tree),
+                        Err(err) => println!("{}", err),
                     }
-                    ReplStatements::SingleExpr(x) => interpreter.print(&x),
-                };
+                    HAD_ERROR.store(false, Ordering::Relaxed);
+                    continue;
+                }
+                pending.push_str(&input);
+                let stmts = repl_interpret(pending.clone());
+                if let ReplStatements::Incomplete = stmts {
+                    continue;
+                }
+                pending.clear();
+                let exit_code = repl_execute(&mut interpreter, &mut resolver, stmts);
+                interpreter
+                    .take_hints()
+                    .iter()
+                    .for_each(|hint| println!("{}", hint));
+                if let Some(code) = exit_code {
+                    std::process::exit(code);
+                }
                 HAD_ERROR.store(false, Ordering::Relaxed);
             }
             Err(error) => println!("error: {}", error),
@@ -70,19 +192,48 @@ pub fn run_prompt() {
 pub enum ReplStatements {
     SingleExpr(Stmt),
     List(Vec<Stmt>),
+    Incomplete,
+}
+
+// Resolves and runs one complete REPL input. A resolver error is printed and the input skipped,
+// keeping the session alive.
+pub fn repl_execute(
+    interpreter: &mut Interpreter,
+    resolver: &mut Resolver,
+    stmts: ReplStatements,
+) -> Option<i32> {
+    let (stmts, print_value) = match stmts {
+        ReplStatements::List(x) => (x, false),
+        ReplStatements::SingleExpr(x) => (vec![x], true),
+        ReplStatements::Incomplete => return None,
+    };
+    match resolver.run(&stmts) {
+        Ok(map) => interpreter.add_expr_ids_depth(map),
+        Err(err) => {
+            println!("{}", err);
+            return None;
+        }
+    }
+
+    if print_value {
+        interpreter.print(&stmts[0])
+    } else {
+        interpreter.interpret(&stmts)
+    }
 }
 
 pub fn repl_interpret(input: String) -> ReplStatements {
     let mut scanner = Scanner::new(input);
     scanner.scan_tokens();
-    let mut parser = Parser::new(&scanner.tokens, true);
+    let mut parser = Parser::new(&scanner.tokens, true).with_incomplete_detection(true);
     let parsed_result = parser.parse();
 
     let errs: Vec<_> = match &parsed_result {
+        ParseResult::Incomplete => return ReplStatements::Incomplete,
         ParseResult::SingleExpr(Err(x)) => vec![x.clone()],
         ParseResult::SingleExpr(_) => vec![],
         ParseResult::List(x) => x
-            .into_iter()
+            .iter()
             .filter_map(|x| x.as_ref().err())
             .cloned()
             .collect::<Vec<LoxError>>(),
@@ -94,6 +245,7 @@ pub fn repl_interpret(input: String) -> ReplStatements {
     }
 
     match parsed_result {
+        ParseResult::Incomplete => unreachable!(),
         ParseResult::List(x) => {
             ReplStatements::List(x.into_iter().filter_map(|x| x.ok()).collect())
         }
@@ -107,6 +259,14 @@ pub fn repl_interpret(input: String) -> ReplStatements {
     }
 }
 
+// Parse tree of a single expression, printed by the `:ast` REPL command without evaluating it
+pub fn repl_ast(source: &str) -> Result<String, LoxError> {
+    let mut scanner = Scanner::new(source.to_string());
+    scanner.scan_tokens();
+    let expr = Parser::new(&scanner.tokens, false).parse_expression()?;
+    Ok(ASTPrinter::print(&expr))
+}
+
 // TODO figureout duplicated code
 pub fn run(input: String) -> Vec<Stmt> {
     let mut scanner = Scanner::new(input);
@@ -116,7 +276,7 @@ pub fn run(input: String) -> Vec<Stmt> {
 
     let list_result = match parsed_result {
         ParseResult::List(x) => x,
-        ParseResult::SingleExpr(_) => unreachable!(), // Interpreting a file doesnt allow expr only without ;,
+        ParseResult::SingleExpr(_) | ParseResult::Incomplete => unreachable!(), // Interpreting a file doesnt allow expr only without ;,
     };
 
     let errs: Vec<_> = list_result
@@ -133,18 +293,33 @@ pub fn run(input: String) -> Vec<Stmt> {
 }
 
 pub fn error(line: usize, message: &str) {
-    report(line, "", message);
+    report(&position(line, 0), "", message);
+}
+
+pub fn error_at(line: usize, column: usize, message: &str) {
+    report(&position(line, column), "", message);
 }
 
 pub fn error_token(token: Token, message: &str) {
     match token.kind {
-        TokenType::Eof => report(token.line, "at end", message),
-        _ => report(token.line, &format!(" at '{}'", token.lexeme), message),
+        TokenType::Eof => report(&token.position(), "at end", message),
+        _ => report(
+            &token.position(),
+            &format!(" at '{}'", token.lexeme),
+            message,
+        ),
     }
 }
 
-fn report(line: usize, location: &str, message: &str) {
-    println!("[line {} ] Error {} : {}", line, location, message);
+// Prints a warning about `token` and returns the printed line, so the caller can keep it
+pub fn warning(token: &Token, message: &str) -> String {
+    let warning = format!("[{}] Warning: {}", token.position(), message);
+    println!("{}", warning);
+    warning
+}
+
+fn report(position: &str, location: &str, message: &str) {
+    println!("[{}] Error {} : {}", position, location, message);
     HAD_ERROR.store(true, Ordering::Relaxed);
 }
 
@@ -152,3 +327,109 @@ pub fn report_runtime(err: LoxError) {
     println!("{}", err);
     HAD_RUNTIME_ERROR.store(true, Ordering::Relaxed);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repl_ast_prints_tree() {
+        assert_eq!(repl_ast("1 + 2 * 3"), Ok("(+ 1 (* 2 3))".to_string()));
+    }
+
+    #[test]
+    fn repl_ast_reports_parse_error() {
+        assert!(repl_ast("1 +").is_err());
+    }
+
+    #[test]
+    fn repl_waits_for_rest_of_function() {
+        let first_line = "fun add(a, b) {\n".to_string();
+        assert!(matches!(
+            repl_interpret(first_line.clone()),
+            ReplStatements::Incomplete
+        ));
+
+        let source = first_line + "  return a + b;\n}\n";
+        match repl_interpret(source) {
+            ReplStatements::List(stmts) => assert!(matches!(stmts[..], [Stmt::Function(..)])),
+            _ => panic!("expected the function declaration"),
+        }
+    }
+
+    #[test]
+    fn repl_reports_mistakes_before_end_of_input() {
+        assert!(matches!(
+            repl_interpret("var = {\n".to_string()),
+            ReplStatements::List(stmts) if stmts.is_empty()
+        ));
+    }
+
+    fn run_repl_lines(lines: &[&str]) -> Interpreter {
+        let mut interpreter = Interpreter::new();
+        let mut resolver = Resolver::new().with_unused_check(false);
+        for line in lines {
+            let stmts = repl_interpret(line.to_string());
+            assert_eq!(repl_execute(&mut interpreter, &mut resolver, stmts), None);
+        }
+        interpreter
+    }
+
+    fn repl_variable(interpreter: &Interpreter, name: &str) -> Result<crate::Object, LoxError> {
+        let token = Token::new(TokenType::Identifier, name.to_string(), 0);
+        interpreter.environment().borrow().get(&token)
+    }
+
+    #[test]
+    fn repl_survives_resolver_error() {
+        let interpreter = run_repl_lines(&["return 1;\n", "var a = 2; print a;\n"]);
+
+        assert_eq!(
+            repl_variable(&interpreter, "a"),
+            Ok(crate::Object::Number(2.0))
+        );
+    }
+
+    #[test]
+    fn repl_calls_function_from_earlier_line() {
+        let interpreter = run_repl_lines(&[
+            "fun double(x) { return x * 2; }\n",
+            "var result = double(21);\n",
+            "result\n",
+        ]);
+
+        assert_eq!(
+            repl_variable(&interpreter, "result"),
+            Ok(crate::Object::Number(42.0))
+        );
+    }
+
+    #[test]
+    fn dump_tokens_of_snippet() {
+        let dump = dump_tokens("var a = 1;\nprint a;".to_string());
+
+        assert_eq!(
+            dump,
+            "[line 1] Var var \n\
+             [line 1] Identifier a \n\
+             [line 1] Equal = \n\
+             [line 1] Number 1 1\n\
+             [line 1] Semicolon ; \n\
+             [line 2] Print print \n\
+             [line 2] Identifier a \n\
+             [line 2] Semicolon ; \n\
+             [line 2] Eof  \n"
+        );
+    }
+
+    #[test]
+    fn print_program_ast() {
+        let stmts = run("fun f(a) { if (a) return 1; else return 2; }\nprint f(true);".to_string());
+
+        assert_eq!(
+            ASTPrinter::print_program(&stmts),
+            "(fun f(a) (if-else a (return 1) (return 2)))\n\
+             (print (call f true))\n"
+        );
+    }
+}
@@ -1,12 +1,39 @@
 use crate::error::Result;
 use crate::interpreter::Interpreter;
+use crate::lox_class::LoxClass;
+use crate::lox_instance::LoxInstance;
 use crate::object::Object;
 use core::fmt::Debug;
 use dyn_clone::DynClone;
+use std::cell::RefCell;
+use std::rc::Rc;
 
-pub trait Callable: Debug + DynClone {
+pub trait Callable: Debug + DynClone + BoxedCallable {
     fn arity(&self) -> usize;
     fn call(&self, arguments: &[Object], environment: &mut Interpreter) -> Result<Object>;
+    fn as_class(&self) -> Option<&LoxClass> {
+        None
+    }
+    // Text of the `///` comment above a user function, returned by the `doc` native
+    fn doc(&self) -> Option<&str> {
+        None
+    }
+    // Turns a callable found on a class into a method of `instance`.
+    // Only functions that can refer to `this` need to do anything.
+    fn bind(&self, _instance: Rc<RefCell<LoxInstance>>) -> Box<dyn Callable> {
+        self.boxed()
+    }
+}
+
+// Lets the default `bind` return a copy of any callable as a trait object
+pub trait BoxedCallable {
+    fn boxed(&self) -> Box<dyn Callable>;
+}
+
+impl<T: Callable + Clone + 'static> BoxedCallable for T {
+    fn boxed(&self) -> Box<dyn Callable> {
+        Box::new(self.clone())
+    }
 }
 
 dyn_clone::clone_trait_object!(Callable);
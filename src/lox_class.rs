@@ -12,12 +12,21 @@ use std::rc::Rc;
 #[derive(Clone, Debug)]
 pub struct LoxClass {
     name: Token,
+    superclass: Option<Box<LoxClass>>,
     methods: HashMap<String, UserFunction>,
 }
 
 impl LoxClass {
-    pub fn new(name: Token, methods: HashMap<String, UserFunction>) -> Self {
-        Self { name, methods }
+    pub fn new(
+        name: Token,
+        superclass: Option<Box<LoxClass>>,
+        methods: HashMap<String, UserFunction>,
+    ) -> Self {
+        Self {
+            name,
+            superclass,
+            methods,
+        }
     }
 
     pub fn name(&self) -> &str {
@@ -25,7 +34,11 @@ impl LoxClass {
     }
 
     pub fn find_method(&self, name: &str) -> Option<UserFunction> {
-        self.methods.get(name).cloned()
+        self.methods.get(name).cloned().or_else(|| {
+            self.superclass
+                .as_ref()
+                .and_then(|superclass| superclass.find_method(name))
+        })
     }
 }
 impl Callable for LoxClass {
@@ -38,12 +51,16 @@ impl Callable for LoxClass {
     fn call(&self, arguments: &[Object], interpreter: &mut Interpreter) -> Result<Object> {
         let instance = Rc::new(RefCell::new(LoxInstance::new(self.clone())));
 
-        self.find_method("init").map(|method| {
+        if let Some(method) = self.find_method("init") {
             method
                 .bind(Rc::clone(&instance))
-                .call(arguments, interpreter)
-        });
+                .call(arguments, interpreter)?;
+        }
 
         Ok(Object::ClassInstance(instance))
     }
+
+    fn as_class(&self) -> Option<&LoxClass> {
+        Some(self)
+    }
 }
@@ -1,5 +1,6 @@
 use crate::error::LoxError;
 use crate::error::Result;
+use crate::lox_callable::Callable;
 use crate::lox_class::LoxClass;
 use crate::token::Token;
 use crate::Object;
@@ -22,6 +23,19 @@ impl LoxInstance {
         }
     }
 
+    pub fn class(&self) -> &LoxClass {
+        &self.class
+    }
+
+    pub fn field(&self, name: &str) -> Option<Object> {
+        self.fields.get(name).cloned()
+    }
+
+    // Whether `name` finds a method of the class, rather than a field shadowing it
+    pub fn is_method(&self, name: &str) -> bool {
+        !self.fields.contains_key(name) && self.class.find_method(name).is_some()
+    }
+
     pub fn get(instance: Rc<RefCell<LoxInstance>>, token: &Token) -> Result<Object> {
         let instance_borrow = instance.borrow();
         instance_borrow
@@ -32,8 +46,7 @@ impl LoxInstance {
                 instance_borrow
                     .class
                     .find_method(&token.lexeme)
-                    .map(|method| method.bind(Rc::clone(&instance)))
-                    .map(|m| Object::Call(Box::new(m)))
+                    .map(|method| Object::Call(method.bind(Rc::clone(&instance))))
             })
             .ok_or_else(|| {
                 LoxError::RuntimeError(
@@ -1,39 +1,26 @@
-#![feature(peekable_next_if)]
-#![feature(hash_drain_filter)]
-
-pub mod ast_printer;
-mod environment;
-pub mod error;
-mod expr;
-mod interpreter;
-mod lox;
-pub mod lox_callable;
-pub mod lox_class;
-pub mod lox_instance;
-mod object;
-pub mod parser;
-pub mod resolver;
-mod scanner;
-mod stmt;
-pub mod token;
-pub mod token_type;
-
-pub use object::Object;
-
+use crafting_interpreters::lox;
 use std::env;
-fn main() {
-    let args = env::args();
+use std::error::Error;
+use std::io::{self, Read};
 
+fn main() {
     // First argument is binary name
-    match args.len() {
-        1 => {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match args.as_slice() {
+        [] => {
             lox::run_prompt();
         }
-        2 => {
-            lox::run_file(args.last().unwrap());
+        [flag, path] if flag == "--ast" => exit_with(lox::run_ast(path.clone())),
+        [flag, path] if flag == "--ast-stats" => exit_with(lox::run_ast_stats(path.clone())),
+        [flag, path] if flag == "--tokens" || flag == "--emit-tokens" => {
+            exit_with(lox::run_tokens(path.clone()))
         }
+        [flag, path] if flag == "--werror" => run_file(path, true),
+        [path] if path == "-" => run_stdin(),
+        [path] => run_file(path, false),
         _ => {
-            println!("Usage: jlox [script]");
+            println!("Usage: jlox [--ast | --ast-stats | --tokens | --emit-tokens | --werror] [script | -]");
             // EX_USAGE (64)	   The command was used incorrectly, e.g., with the
             // wrong number of arguments, a bad flag, a bad syntax
             // in a parameter, or whatever.
@@ -41,3 +28,30 @@ fn main() {
         }
     };
 }
+
+fn run_file(path: &str, warnings_as_errors: bool) {
+    exit_with(lox::run_file(path.to_string(), warnings_as_errors))
+}
+
+// Exits with the code of a finished run, or reports why its file couldn't be read
+fn exit_with(result: Result<lox::RunStatus, Box<dyn Error>>) -> ! {
+    match result {
+        Ok(status) => std::process::exit(status.exit_code()),
+        Err(err) => {
+            println!("error: {}", err);
+            // EX_NOINPUT (66) An input file did not exist or was not readable.
+            std::process::exit(66);
+        }
+    }
+}
+
+// `jlox -` runs the whole of stdin as one script, unlike the line by line prompt
+fn run_stdin() {
+    let mut source = String::new();
+    if let Err(err) = io::stdin().read_to_string(&mut source) {
+        println!("error: {}", err);
+        // EX_NOINPUT (66) An input file did not exist or was not readable.
+        std::process::exit(66);
+    }
+    std::process::exit(lox::run_source(source, false).exit_code());
+}
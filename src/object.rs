@@ -3,8 +3,9 @@ use crate::lox_instance::LoxInstance;
 use core::fmt::Debug;
 use std::cell::RefCell;
 use std::cmp::PartialEq;
+use std::collections::HashMap;
 use std::fmt::Display;
-use std::rc::Rc;
+use std::rc::{Rc, Weak};
 
 #[derive(Debug, Clone)]
 pub enum Object {
@@ -13,18 +14,34 @@ pub enum Object {
     Number(f64),
     Call(Box<dyn Callable>),
     ClassInstance(Rc<RefCell<LoxInstance>>),
+    Map(Rc<RefCell<HashMap<String, Object>>>),
+    WeakRef(Weak<RefCell<LoxInstance>>), // doesn't keep the instance alive
     Nil,
 }
 impl Object {
     pub fn is_truphy(&self) -> bool {
         match self {
-            Object::Boolean(x) => x.clone(),
+            Object::Boolean(x) => *x,
             Object::Nil => false,
             _ => true,
         }
     }
 }
 
+// Whole numbers print without a fractional part, other values with the fewest digits
+// that still read back as the same number.
+pub fn format_number(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "Infinity" } else { "-Infinity" }.to_string()
+    } else if value.fract() == 0.0 {
+        format!("{:.0}", value)
+    } else {
+        value.to_string()
+    }
+}
+
 impl PartialEq for Object {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
@@ -32,20 +49,87 @@ impl PartialEq for Object {
             (Object::Number(x), Object::Number(y)) => x == y,
             (Object::String(x), Object::String(y)) => x == y,
             (Object::Nil, Object::Nil) => true,
+            (Object::Map(x), Object::Map(y)) => Rc::ptr_eq(x, y),
+            (Object::ClassInstance(x), Object::ClassInstance(y)) => Rc::ptr_eq(x, y),
+            (Object::WeakRef(x), Object::WeakRef(y)) => Weak::ptr_eq(x, y),
+            // boxed callables have no identity to compare, so functions are never equal
             (_, _) => false,
         }
     }
 }
 
+impl Object {
+    // `open` holds the maps being printed around this value, so a map that contains itself
+    // prints `{...}` where it repeats instead of recursing forever
+    fn fmt_nested(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        open: &mut Vec<*const RefCell<HashMap<String, Object>>>,
+    ) -> std::fmt::Result {
+        let map = match self {
+            Object::Map(map) => map,
+            other => return write!(f, "{}", other),
+        };
+        if open.contains(&Rc::as_ptr(map)) {
+            return write!(f, "{{...}}");
+        }
+
+        open.push(Rc::as_ptr(map));
+        let map = map.borrow();
+        let mut keys: Vec<&String> = map.keys().collect();
+        keys.sort();
+        write!(f, "{{")?;
+        for (index, key) in keys.into_iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}: ", key)?;
+            map[key].fmt_nested(f, open)?;
+        }
+        open.pop();
+        write!(f, "}}")
+    }
+}
+
 impl Display for Object {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Object::Boolean(x) => write!(f, "{}", x),
             Object::String(x) => write!(f, "{}", x),
-            Object::Number(x) => write!(f, "{}", x),
+            Object::Number(x) => write!(f, "{}", format_number(*x)),
             Object::Call(_) => write!(f, "function"),
             Object::ClassInstance(x) => write!(f, "{}", x.borrow()),
+            Object::Map(_) => self.fmt_nested(f, &mut vec![]),
+            Object::WeakRef(weak) => match weak.upgrade() {
+                Some(instance) => write!(f, "weak {}", instance.borrow()),
+                None => write!(f, "weak (collected)"),
+            },
             Object::Nil => write!(f, "nil"),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn display_whole_number() {
+        assert_eq!(Object::Number(1.0).to_string(), "1");
+    }
+
+    #[test]
+    fn display_fractional_numbers() {
+        assert_eq!(Object::Number(1.5).to_string(), "1.5");
+        assert_eq!(Object::Number(0.1).to_string(), "0.1");
+    }
+
+    #[test]
+    fn display_large_whole_number() {
+        assert_eq!(Object::Number(100000000.0).to_string(), "100000000");
+        assert_eq!(
+            Object::Number(12345678901234567890.0).to_string(),
+            "12345678901234567168"
+        );
+    }
+}
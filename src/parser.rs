@@ -2,10 +2,11 @@ use super::error;
 use super::error::{LoxError, Result};
 use super::expr::Expr;
 use super::lox;
-use super::stmt::{Function, Stmt};
+use super::stmt::{Case, Function, Signature, Stmt};
 use super::token::Token;
 use super::token_type::TokenType;
-use std::iter::Peekable;
+use std::collections::HashMap;
+use std::iter::{Filter, Peekable};
 use std::slice::Iter;
 use std::sync::atomic::{AtomicU64, Ordering};
 
@@ -15,33 +16,72 @@ fn get_next_id() -> u64 {
 }
 
 const MAX_FUN_ARGUMENTS: usize = 255;
+
+// Doc comments can show up anywhere, so they're taken out of the way before parsing
+type Tokens<'a> = Peekable<Filter<Iter<'a, Token>, fn(&&'a Token) -> bool>>;
+
 pub struct Parser<'a> {
-    tokens_iter: Peekable<Iter<'a, Token>>,
+    tokens_iter: Tokens<'a>,
+    docs: HashMap<(usize, usize), String>, // doc comment text by the position of what follows
     allow_only_expression: bool,
     found_only_expr: bool, // flag that signals if a expression only was found(without ending ;)
+    lenient: bool,         // inserts missing ; before a new statement instead of failing
+    warnings: Vec<String>,
+    last_line: usize, // line reported when the tokens run out before an Eof token
+    detect_incomplete: bool, // errors at Eof mean more input is needed instead of a mistake
+    reached_end: bool,
+    had_error: bool,
 }
 
 #[derive(Clone)]
 pub enum ParseResult {
     List(Vec<Result<Stmt>>),
     SingleExpr(Result<Stmt>),
+    Incomplete, // only produced when incomplete input detection is enabled
 }
 
 #[derive(Debug)]
 pub enum FunctionKind {
     Function,
     Method,
+    Lambda,
 }
 
 impl<'a> Parser<'a> {
     pub fn new(tokens: &'a [Token], allow_only_expression: bool) -> Self {
         Self {
-            tokens_iter: tokens.iter().peekable(),
+            tokens_iter: tokens
+                .iter()
+                .filter(is_not_doc as fn(&&'a Token) -> bool)
+                .peekable(),
+            docs: collect_docs(tokens),
             allow_only_expression,
             found_only_expr: false,
+            lenient: false,
+            warnings: vec![],
+            last_line: tokens.last().map(|token| token.line).unwrap_or(1),
+            detect_incomplete: false,
+            reached_end: false,
+            had_error: false,
         }
     }
 
+    // The REPL keeps reading lines while a statement is unfinished, like an open `{`.
+    // Errors at the end of input are then left unreported and `parse` returns `Incomplete`.
+    pub fn with_incomplete_detection(mut self, enabled: bool) -> Self {
+        self.detect_incomplete = enabled;
+        self
+    }
+
+    pub fn with_lenient_semicolons(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
     pub fn parse(&mut self) -> ParseResult {
         let mut parsed_list = Vec::new();
 
@@ -52,34 +92,52 @@ impl<'a> Parser<'a> {
 
             let declaration = self.declaration();
 
-            if self.found_only_expr {
+            // a trailing expression after other statements is kept as part of the list
+            if self.found_only_expr && parsed_list.is_empty() && !self.is_incomplete() {
                 return ParseResult::SingleExpr(declaration);
             }
 
             parsed_list.push(declaration);
         }
 
+        if self.is_incomplete() {
+            return ParseResult::Incomplete;
+        }
         ParseResult::List(parsed_list)
     }
 
+    fn is_incomplete(&self) -> bool {
+        self.detect_incomplete && self.reached_end && !self.had_error
+    }
+
+    // Parses the tokens as exactly one expression, without a trailing ;
+    pub fn parse_expression(&mut self) -> Result<Expr> {
+        let expr = self.expression()?;
+        self.consume(TokenType::Eof, "Expect end of expression")?;
+        Ok(expr)
+    }
+
     fn declaration(&mut self) -> Result<Stmt> {
-        let next_declaration_token = self
-            .tokens_iter
-            .next_if(|token| {
-                matches!(
-                    token.kind,
-                    TokenType::Fun | TokenType::Var | TokenType::Class
-                )
-            })
-            .map(|t| &t.kind);
+        // `fun` directly followed by `(` starts an anonymous function expression instead
+        let is_lambda = self.peek_second().map(|t| &t.kind) == Some(&TokenType::LeftParen);
+        let next_declaration_token = self.tokens_iter.next_if(|token| match token.kind {
+            TokenType::Fun => !is_lambda,
+            TokenType::Var | TokenType::Class => true,
+            _ => false,
+        });
 
         let result = match next_declaration_token {
-            Some(TokenType::Fun) => self
-                .fun_declaration(FunctionKind::Function)
-                .map(|(token, body, parameters)| Stmt::Function(token, body, parameters)),
-            Some(TokenType::Var) => self.var_declaration(),
-            Some(TokenType::Class) => self.class_declaration(),
-            _ => self.statement(),
+            Some(token) if token.kind == TokenType::Fun => {
+                let doc = self.doc_before(token);
+                self.fun_declaration(FunctionKind::Function, doc).map(
+                    |(token, parameters, body, signature)| {
+                        Stmt::Function(token, parameters, body, signature)
+                    },
+                )
+            }
+            Some(token) if token.kind == TokenType::Var => self.var_declaration(),
+            Some(_) => self.class_declaration(),
+            None => self.statement(),
         };
 
         if result.is_err() {
@@ -93,6 +151,18 @@ impl<'a> Parser<'a> {
         let class_name = self
             .consume(TokenType::Identifier, "expected class name")?
             .clone();
+
+        let superclass = if self
+            .tokens_iter
+            .next_if(|t| t.kind == TokenType::Less)
+            .is_some()
+        {
+            let name = self.consume(TokenType::Identifier, "Expected superclass name")?;
+            Some(Expr::Variable(name.clone(), get_next_id()))
+        } else {
+            None
+        };
+
         self.consume(TokenType::LeftBrace, "Expected '{' after class name")?;
 
         let mut methods = vec![];
@@ -103,7 +173,12 @@ impl<'a> Parser<'a> {
             .map(|t| t.kind != TokenType::RightBrace)
             .unwrap_or(false)
         {
-            methods.push(self.fun_declaration(FunctionKind::Method)?)
+            let doc = self
+                .tokens_iter
+                .peek()
+                .copied()
+                .and_then(|token| self.doc_before(token));
+            methods.push(self.fun_declaration(FunctionKind::Method, doc)?)
         }
 
         self.consume(
@@ -113,20 +188,39 @@ impl<'a> Parser<'a> {
 
         Ok(Stmt::Class {
             token: class_name,
+            superclass,
             methods,
         })
     }
 
-    fn fun_declaration(&mut self, kind: FunctionKind) -> Result<Function> {
+    fn fun_declaration(&mut self, kind: FunctionKind, doc: Option<String>) -> Result<Function> {
         let token_name = self
             .consume(TokenType::Identifier, &format!("Expected {:?} name", kind))?
             .clone();
+        let (parameters, body, mut signature) = self.function_body(&token_name, kind)?;
+        signature.doc = doc;
+
+        Ok((token_name, parameters, body, signature))
+    }
+
+    // Doc comment of the declaration starting at `token`
+    fn doc_before(&self, token: &Token) -> Option<String> {
+        self.docs.get(&(token.line, token.column)).cloned()
+    }
+
+    // Parses everything after the function name: parameters and the body block
+    fn function_body(
+        &mut self,
+        token: &Token,
+        kind: FunctionKind,
+    ) -> Result<(Vec<Token>, Vec<Stmt>, Signature)> {
         self.consume(
             TokenType::LeftParen,
             &format!("Expected '(' after {:?}", kind),
         )?;
 
         let mut parameters = vec![];
+        let mut signature = Signature::default();
 
         if self
             .tokens_iter
@@ -137,7 +231,7 @@ impl<'a> Parser<'a> {
             loop {
                 if parameters.len() > MAX_FUN_ARGUMENTS {
                     return Err(LoxError::RuntimeError(
-                        token_name,
+                        token.clone(),
                         "Reached maximum number of parameters(255)".to_string(),
                     ));
                 }
@@ -145,6 +239,9 @@ impl<'a> Parser<'a> {
                     .consume(TokenType::Identifier, "Expected identifier")?
                     .clone();
                 parameters.push(param);
+                signature
+                    .params
+                    .push(self.type_annotation(TokenType::Colon)?);
 
                 if self
                     .tokens_iter
@@ -156,10 +253,8 @@ impl<'a> Parser<'a> {
             }
         }
 
-        self.consume(
-            TokenType::RightParen,
-            &format!("Expected ')' after {:?} parameters.", kind),
-        )?;
+        self.consume_with_alternatives(TokenType::RightParen, &[TokenType::Comma])?;
+        signature.returns = self.type_annotation(TokenType::Arrow)?.map(Box::new);
         self.consume(
             TokenType::LeftBrace,
             &format!("Expected '{{' before {:?} body.", kind),
@@ -169,13 +264,24 @@ impl<'a> Parser<'a> {
             x => vec![x],
         };
 
-        Ok((token_name, parameters, body))
+        Ok((parameters, body, signature))
+    }
+
+    // Type name after `marker`, as in `: Number` or `-> Number`, when the marker comes next
+    fn type_annotation(&mut self, marker: TokenType) -> Result<Option<Token>> {
+        if self.tokens_iter.next_if(|t| t.kind == marker).is_none() {
+            return Ok(None);
+        }
+
+        let name = self.consume(TokenType::Identifier, "Expected type name")?;
+        Ok(Some(name.clone()))
     }
 
     fn var_declaration(&mut self) -> Result<Stmt> {
         let name = self
             .consume(TokenType::Identifier, "Expect variable name")?
             .clone();
+        let annotation = self.type_annotation(TokenType::Colon)?.map(Box::new);
 
         let mut initializer = None;
         if self
@@ -186,9 +292,9 @@ impl<'a> Parser<'a> {
             initializer = Some(self.expression()?);
         }
 
-        self.consume(TokenType::Semicolon, "Expect ; after variable declaration")?;
+        self.consume_semicolon("Expect ; after variable declaration")?;
 
-        Ok(Stmt::Var(name, initializer))
+        Ok(Stmt::Var(name, annotation, initializer))
     }
 
     fn statement(&mut self) -> Result<Stmt> {
@@ -213,7 +319,7 @@ impl<'a> Parser<'a> {
             .next_if(|t| t.kind == TokenType::While)
             .is_some()
         {
-            return self.while_stmt();
+            return self.while_stmt(true);
         }
 
         if self
@@ -221,23 +327,38 @@ impl<'a> Parser<'a> {
             .next_if(|t| t.kind == TokenType::For)
             .is_some()
         {
-            return self.for_stmt();
+            return self.for_stmt(true);
         }
 
         if self
             .tokens_iter
-            .next_if(|t| t.kind == TokenType::LeftBrace)
+            .next_if(|t| t.kind == TokenType::Switch)
             .is_some()
         {
-            return self.block();
+            return self.switch_stmt();
         }
 
         if self
             .tokens_iter
-            .next_if(|t| t.kind == TokenType::Return)
+            .next_if(|t| t.kind == TokenType::LeftBrace)
             .is_some()
         {
-            return self.return_stmt();
+            return self.block();
+        }
+
+        if let Some(keyword) = self.tokens_iter.next_if(|t| t.kind == TokenType::Return) {
+            return self.return_stmt(keyword.clone());
+        }
+
+        if let Some(token) = self.tokens_iter.next_if(|t| t.kind == TokenType::Break) {
+            let token = token.clone();
+            return self.break_stmt(token);
+        }
+
+        if let Some(token) = self.tokens_iter.next_if(|t| t.kind == TokenType::Continue) {
+            let token = token.clone();
+            self.consume_semicolon("Expected ; after continue")?;
+            return Ok(Stmt::Continue(token));
         }
 
         self.expr_stmt()
@@ -251,7 +372,7 @@ impl<'a> Parser<'a> {
             "expected ')' to close if conditional",
         )?;
 
-        let then_branch = self.statement()?;
+        let then_branch = self.statement_before_else()?;
         let else_branch = if self
             .tokens_iter
             .next_if(|t| t.kind == TokenType::Else)
@@ -277,7 +398,7 @@ impl<'a> Parser<'a> {
             self.found_only_expr = true;
             return Ok(Stmt::Expression(expr));
         } else {
-            self.consume(TokenType::Semicolon, "Expected ; after expression")?;
+            self.consume_semicolon("Expected ; after expression")?;
         }
 
         Ok(Stmt::Expression(expr))
@@ -308,41 +429,149 @@ impl<'a> Parser<'a> {
     }
 
     fn print_stmt(&mut self) -> Result<Stmt> {
-        let expr = self.expression()?;
+        let mut values = vec![self.expression()?];
+        while self
+            .tokens_iter
+            .next_if(|token| token.kind == TokenType::Comma)
+            .is_some()
+        {
+            values.push(self.expression()?);
+        }
 
-        self.consume(TokenType::Semicolon, "Expected ; after value")?;
+        self.consume_semicolon("Expected ; after value")?;
 
-        Ok(Stmt::Print(expr))
+        Ok(Stmt::Print(values))
     }
 
-    fn while_stmt(&mut self) -> Result<Stmt> {
+    // Only the matching case runs, there's no fallthrough to the next one
+    fn switch_stmt(&mut self) -> Result<Stmt> {
+        self.consume(TokenType::LeftParen, "Expected '(' after switch")?;
+        let value = self.expression()?;
+        self.consume(TokenType::RightParen, "Expected ')' after switch value")?;
+        self.consume(TokenType::LeftBrace, "Expected '{' before switch cases")?;
+
+        let mut cases: Vec<Case> = vec![];
+        let mut default = None;
+        while let Some(token) = self.tokens_iter.next_if(|t| switch_keyword(t).is_some()) {
+            let token = token.clone();
+            if switch_keyword(&token) == Some(TokenType::Case) {
+                let case = self.expression()?;
+                self.consume(TokenType::Colon, "Expected ':' after case value")?;
+                cases.push((case, self.case_body()?));
+            } else if default.is_none() {
+                self.consume(TokenType::Colon, "Expected ':' after default")?;
+                default = Some(self.case_body()?);
+            } else {
+                return Err(error(token, "A switch can only have one default"));
+            }
+        }
+
+        self.consume_with_alternatives(
+            TokenType::RightBrace,
+            &[TokenType::Case, TokenType::Default],
+        )?;
+
+        Ok(Stmt::Switch(value, cases, default))
+    }
+
+    fn case_body(&mut self) -> Result<Vec<Stmt>> {
+        let mut statements = vec![];
+        while self
+            .tokens_iter
+            .peek()
+            .map(|token| {
+                switch_keyword(token).is_none()
+                    && !matches!(token.kind, TokenType::RightBrace | TokenType::Eof)
+            })
+            .unwrap_or(false)
+        {
+            statements.push(self.declaration()?);
+        }
+        Ok(statements)
+    }
+
+    fn while_stmt(&mut self, allow_else: bool) -> Result<Stmt> {
         self.consume(TokenType::LeftParen, "Expected '(' before condition")?;
         let cond = self.expression()?;
         self.consume(TokenType::RightParen, "Expected ')' after condition")?;
 
-        let block = self.statement()?;
+        let (block, else_branch) = self.loop_body(allow_else)?;
 
-        Ok(Stmt::While(cond, Box::new(block)))
+        Ok(Stmt::While {
+            condition: cond,
+            body: Box::new(block),
+            increment: None,
+            else_branch,
+        })
     }
 
-    fn return_stmt(&mut self) -> Result<Stmt> {
+    // Branch of an `if` that may be followed by its `else`. A loop written there without braces
+    // leaves that `else` to the `if`, as it did before loops could have one.
+    fn statement_before_else(&mut self) -> Result<Stmt> {
+        if self
+            .tokens_iter
+            .next_if(|t| t.kind == TokenType::While)
+            .is_some()
+        {
+            return self.while_stmt(false);
+        }
+
+        if self
+            .tokens_iter
+            .next_if(|t| t.kind == TokenType::For)
+            .is_some()
+        {
+            return self.for_stmt(false);
+        }
+
+        self.statement()
+    }
+
+    fn loop_body(&mut self, allow_else: bool) -> Result<(Stmt, Option<Box<Stmt>>)> {
+        if !allow_else {
+            return Ok((self.statement_before_else()?, None));
+        }
+
+        let body = self.statement()?;
+        let else_branch = if self
+            .tokens_iter
+            .next_if(|t| t.kind == TokenType::Else)
+            .is_some()
+        {
+            Some(Box::new(self.statement()?))
+        } else {
+            None
+        };
+        Ok((body, else_branch))
+    }
+
+    fn break_stmt(&mut self, token: Token) -> Result<Stmt> {
+        self.consume_semicolon("Expected ; after break")?;
+
+        Ok(Stmt::Break(token))
+    }
+
+    fn return_stmt(&mut self, keyword: Token) -> Result<Stmt> {
         let expr = if self
             .tokens_iter
             .peek()
             .map(|t| t.kind != TokenType::Semicolon)
             .unwrap_or(false)
         {
-            self.expression()?
+            Some(self.expression()?)
         } else {
-            Expr::Nil
+            None
         };
 
-        let token = self.consume(TokenType::Semicolon, "Expected ; after return expression")?;
+        self.consume_semicolon("Expected ; after return expression")?;
 
-        Ok(Stmt::Return(token.clone(), expr))
+        Ok(Stmt::Return(keyword, expr))
     }
 
-    fn for_stmt(&mut self) -> Result<Stmt> {
+    // The loop variable is declared once, outside of the while, so closures created in the body
+    // all share it and see its last value. Declaring a variable in the body gives each
+    // iteration its own binding, since the body block runs in a fresh scope every time.
+    fn for_stmt(&mut self, allow_else: bool) -> Result<Stmt> {
         // desugar for into while
         self.consume(TokenType::LeftParen, "Expected '(' after for")?;
         let initializer = match self
@@ -373,17 +602,14 @@ impl<'a> Parser<'a> {
 
         self.consume(TokenType::RightParen, "Expected ')' after for clauses")?;
 
-        let mut block = self.statement()?;
+        let (body, else_branch) = self.loop_body(allow_else)?;
 
-        if let Some(increment) = increment {
-            block = Stmt::Block(vec![block, Stmt::Expression(increment)]);
-        }
-
-        if let Some(condition) = condition {
-            block = Stmt::While(condition, Box::new(block));
-        } else {
-            block = Stmt::While(Expr::Boolean(true), Box::new(block));
-        }
+        let mut block = Stmt::While {
+            condition: condition.unwrap_or(Expr::Boolean(true)),
+            body: Box::new(body),
+            increment: increment.map(Box::new),
+            else_branch,
+        };
 
         if let Some(initializer) = initializer {
             block = Stmt::Block(vec![initializer, block]);
@@ -397,24 +623,84 @@ impl<'a> Parser<'a> {
     }
 
     fn assignment(&mut self) -> Result<Expr> {
-        let expr = self.conditional()?;
+        let expr = self.pipeline()?;
 
         if let Some(equals) = self
             .tokens_iter
             .next_if(|token| token.kind == TokenType::Equal)
         {
-            let value = self.conditional()?;
+            let value = self.pipeline()?;
 
             if let Expr::Variable(token, _) = expr {
                 return Ok(Expr::Assign(token, Box::new(value), get_next_id()));
             } else if let Expr::Get(object, field) = expr {
                 return Ok(Expr::Set(object, field, Box::new(value)));
+            } else if let Expr::Index(object, bracket, index) = expr {
+                return Ok(Expr::IndexSet(object, bracket, index, Box::new(value)));
             }
 
-            error(equals.clone(), "Invalid assignment target");
+            return Err(error(equals.clone(), "Invalid assignment target"));
         }
 
-        return Ok(expr);
+        if let Some(compound) = self.tokens_iter.next_if(|token| {
+            matches!(
+                token.kind,
+                TokenType::PlusEqual
+                    | TokenType::MinusEqual
+                    | TokenType::StarEqual
+                    | TokenType::SlashEqual
+            )
+        }) {
+            return self.compound_assignment(expr, compound.clone());
+        }
+
+        Ok(expr)
+    }
+
+    // `x += y` is sugar for `x = x + y`. Fields get their own expression so the object is evaluated once.
+    fn compound_assignment(&mut self, target: Expr, compound: Token) -> Result<Expr> {
+        let (kind, lexeme) = match compound.kind {
+            TokenType::PlusEqual => (TokenType::Plus, "+"),
+            TokenType::MinusEqual => (TokenType::Minus, "-"),
+            TokenType::StarEqual => (TokenType::Star, "*"),
+            _ => (TokenType::Slash, "/"),
+        };
+        let operator = Token {
+            kind,
+            lexeme: lexeme.to_string(),
+            ..compound.clone()
+        };
+        let value = self.pipeline()?;
+
+        match target {
+            Expr::Variable(token, _) => {
+                let current = Expr::Variable(token.clone(), get_next_id());
+                let value = Expr::Binary(Box::new(current), operator, Box::new(value));
+                Ok(Expr::Assign(token, Box::new(value), get_next_id()))
+            }
+            Expr::Get(object, field) => Ok(Expr::CompoundSet(
+                object,
+                field,
+                Box::new(operator),
+                Box::new(value),
+            )),
+            _ => Err(error(compound, "Invalid assignment target")),
+        }
+    }
+
+    // `x |> f |> g` is sugar for `g(f(x))`
+    fn pipeline(&mut self) -> Result<Expr> {
+        let mut expr = self.conditional()?;
+
+        while let Some(pipe) = self
+            .tokens_iter
+            .next_if(|token| token.kind == TokenType::PipeGreater)
+        {
+            let function = self.conditional()?;
+            expr = Expr::Call(Box::new(function), pipe.clone(), vec![expr]);
+        }
+
+        Ok(expr)
     }
 
     fn conditional(&mut self) -> Result<Expr> {
@@ -442,18 +728,34 @@ impl<'a> Parser<'a> {
     }
 
     fn logic_or(&mut self) -> Result<Expr> {
-        let mut left = self.logic_and()?;
+        let mut left = self.logic_xor()?;
 
         while self
             .tokens_iter
             .next_if(|token| token.kind == TokenType::Or)
             .is_some()
         {
-            let right = self.logic_and()?;
+            let right = self.logic_xor()?;
             left = Expr::LogicOr(Box::new(left), Box::new(right));
         }
 
-        return Ok(left);
+        Ok(left)
+    }
+
+    // binds tighter than `or` and looser than `and`, like `|`, `^` and `&` in C
+    fn logic_xor(&mut self) -> Result<Expr> {
+        let mut left = self.logic_and()?;
+
+        while self
+            .tokens_iter
+            .next_if(|token| token.kind == TokenType::Xor)
+            .is_some()
+        {
+            let right = self.logic_and()?;
+            left = Expr::LogicXor(Box::new(left), Box::new(right));
+        }
+
+        Ok(left)
     }
 
     fn logic_and(&mut self) -> Result<Expr> {
@@ -468,7 +770,7 @@ impl<'a> Parser<'a> {
             left = Expr::LogicAnd(Box::new(left), Box::new(right))
         }
 
-        return Ok(left);
+        Ok(left)
     }
 
     fn equality(&mut self) -> Result<Expr> {
@@ -572,6 +874,14 @@ impl<'a> Parser<'a> {
             {
                 let name = self.consume(TokenType::Identifier, "Expect property name after '.'")?;
                 expr = Expr::Get(Box::new(expr), name.clone());
+            } else if self
+                .tokens_iter
+                .next_if(|t| t.kind == TokenType::LeftBracket)
+                .is_some()
+            {
+                let index = self.expression()?;
+                let bracket = self.consume(TokenType::RightBracket, "Expect ']' after index")?;
+                expr = Expr::Index(Box::new(expr), bracket.clone(), Box::new(index));
             } else {
                 break;
             }
@@ -608,7 +918,8 @@ impl<'a> Parser<'a> {
             }
         }
 
-        let paren_token = self.consume(TokenType::RightParen, "Expected ')' after arguments")?;
+        let paren_token =
+            self.consume_with_alternatives(TokenType::RightParen, &[TokenType::Comma])?;
 
         Ok(Expr::Call(Box::new(expr), paren_token.clone(), arguments))
     }
@@ -620,32 +931,169 @@ impl<'a> Parser<'a> {
                 TokenType::True => Ok(Expr::Boolean(true)),
                 TokenType::Nil => Ok(Expr::Nil),
                 TokenType::This => Ok(Expr::This(token.clone(), get_next_id())),
+                TokenType::Fun => {
+                    // annotations are accepted on lambdas but not kept
+                    let (parameters, body, _) = self.function_body(token, FunctionKind::Lambda)?;
+                    Ok(Expr::Lambda(parameters, body))
+                }
+                TokenType::Super => {
+                    self.consume(TokenType::Dot, "Expected '.' after 'super'")?;
+                    let method =
+                        self.consume(TokenType::Identifier, "Expected superclass method name")?;
+                    Ok(Expr::Super(token.clone(), method.clone(), get_next_id()))
+                }
                 TokenType::Number(value) => Ok(Expr::Number(*value)),
                 TokenType::String(value) => Ok(Expr::String(value.to_string())),
                 TokenType::Identifier => Ok(Expr::Variable(token.clone(), get_next_id())),
+                TokenType::LeftBrace => self.map_literal(token),
                 TokenType::LeftParen => {
                     let expr = self.expression()?;
                     self.consume(TokenType::RightParen, "Expect ')' after expression")?;
                     Ok(Expr::Grouping(Box::new(expr)))
                 }
-                _ => Err(error((*token).clone(), "expected expression")),
+                _ => {
+                    self.report(token, "expected expression");
+                    Err(LoxError::ExpectedExpression(token.clone()))
+                }
             },
-            None => todo!(),
+            None => Err(self.end_of_tokens_error("Expected expression")),
+        }
+    }
+
+    fn map_literal(&mut self, brace: &Token) -> Result<Expr> {
+        let mut entries = vec![];
+
+        if self
+            .tokens_iter
+            .next_if(|token| token.kind == TokenType::RightBrace)
+            .is_none()
+        {
+            loop {
+                let key = self.expression()?;
+                self.consume(TokenType::Colon, "Expect ':' after map key")?;
+                let value = self.expression()?;
+                entries.push((key, value));
+
+                if self
+                    .tokens_iter
+                    .next_if(|token| token.kind == TokenType::Comma)
+                    .is_none()
+                {
+                    break;
+                }
+            }
+            self.consume_with_alternatives(TokenType::RightBrace, &[TokenType::Comma])?;
         }
+
+        Ok(Expr::Map(brace.clone(), entries))
     }
 
     // --- helper functions ---
+    fn peek_second(&self) -> Option<&'a Token> {
+        let mut lookahead = self.tokens_iter.clone();
+        lookahead.next();
+        lookahead.next()
+    }
+
     fn consume(&mut self, token_type: TokenType, error_message: &str) -> error::Result<&Token> {
         if let Some(token) = self.tokens_iter.peek() {
             if token.kind == token_type {
                 return Ok(self.tokens_iter.next().unwrap());
             }
 
-            let err = error((**token).clone(), error_message);
-            return Err(err);
+            let token = (*token).clone();
+            self.report(&token, error_message);
+            return Err(LoxError::ExpectedToken {
+                expected: token_type,
+                found: token,
+                message: error_message.to_string(),
+            });
         }
 
-        todo!()
+        Err(self.end_of_tokens_error(error_message))
+    }
+
+    fn consume_semicolon(&mut self, error_message: &str) -> error::Result<Token> {
+        let next_starts_statement = self
+            .tokens_iter
+            .peek()
+            .map(|token| {
+                matches!(
+                    token.kind,
+                    TokenType::Class
+                        | TokenType::Fun
+                        | TokenType::Var
+                        | TokenType::For
+                        | TokenType::If
+                        | TokenType::While
+                        | TokenType::Switch
+                        | TokenType::Print
+                        | TokenType::Return
+                        | TokenType::Break
+                        | TokenType::Continue
+                        | TokenType::Identifier
+                )
+            })
+            .unwrap_or(false);
+
+        if self.lenient && next_starts_statement {
+            let next = self.tokens_iter.peek().unwrap(); // safe unwrap because of the check above
+            let message = format!(
+                "{}, inserted missing ';' before '{}'",
+                error_message, next.lexeme
+            );
+            self.warnings.push(lox::warning(next, &message));
+            return Ok(Token::new(TokenType::Semicolon, ";".to_string(), next.line));
+        }
+
+        self.consume(TokenType::Semicolon, error_message).cloned()
+    }
+
+    // Like consume, but the error message lists every token that would have been valid at this point
+    fn consume_with_alternatives(
+        &mut self,
+        token_type: TokenType,
+        alternatives: &[TokenType],
+    ) -> error::Result<&Token> {
+        let found = match self.tokens_iter.peek() {
+            Some(token) if token.kind == token_type => return Ok(self.tokens_iter.next().unwrap()),
+            Some(token) => (*token).clone(),
+            None => return Err(self.end_of_tokens_error(&format!("Expected '{}'", token_type))),
+        };
+
+        let expected = std::iter::once(&token_type)
+            .chain(alternatives)
+            .map(|kind| format!("'{}'", kind))
+            .collect::<Vec<String>>()
+            .join(" or ");
+        let found_text = match found.kind {
+            TokenType::Eof => found.kind.to_string(),
+            _ => format!("'{}'", found.lexeme),
+        };
+        let message = format!("Expected {}, found {}.", expected, found_text);
+
+        self.report(&found, &message);
+        Err(LoxError::ExpectedToken {
+            expected: token_type,
+            found,
+            message,
+        })
+    }
+
+    fn report(&mut self, token: &Token, message: &str) {
+        if token.kind != TokenType::Eof {
+            self.had_error = true;
+        } else if self.detect_incomplete {
+            self.reached_end = true;
+            return;
+        }
+        lox::error_token(token.clone(), message);
+    }
+
+    fn end_of_tokens_error(&self, message: &str) -> LoxError {
+        let message = format!("{}, found end of input", message);
+        lox::error(self.last_line, &message);
+        LoxError::ParserError(self.last_line, message)
     }
 
     fn synchronize(&mut self) {
@@ -659,6 +1107,7 @@ impl<'a> Parser<'a> {
                         | TokenType::For
                         | TokenType::If
                         | TokenType::While
+                        | TokenType::Switch
                         | TokenType::Print
                         | TokenType::Return
                 )
@@ -671,8 +1120,449 @@ impl<'a> Parser<'a> {
     }
 }
 
+// `case` and `default` are only keywords where a switch arm can start, so they can still be
+// used as names everywhere else
+fn switch_keyword(token: &Token) -> Option<TokenType> {
+    match (&token.kind, token.lexeme.as_str()) {
+        (TokenType::Identifier, "case") => Some(TokenType::Case),
+        (TokenType::Identifier, "default") => Some(TokenType::Default),
+        _ => None,
+    }
+}
+
 fn error(token: Token, message: &str) -> error::LoxError {
     let line = token.line;
     lox::error_token(token, message);
     error::LoxError::ParserError(line, message.to_string())
 }
+
+fn is_not_doc(token: &&Token) -> bool {
+    token.kind != TokenType::DocComment
+}
+
+// Consecutive `///` lines are joined into one doc, which belongs to the next token even when
+// blank lines come between them. Of several docs in a row only the last one is kept.
+// A `///` after code on the same line isn't a doc.
+fn collect_docs(tokens: &[Token]) -> HashMap<(usize, usize), String> {
+    let mut docs = HashMap::new();
+    let mut pending: Option<(usize, String)> = None; // line the doc ends on and its text
+    let starts_line = |index: usize| index == 0 || tokens[index - 1].line < tokens[index].line;
+    for (index, token) in tokens.iter().enumerate() {
+        if is_not_doc(&token) {
+            if let Some((_, doc)) = pending.take() {
+                docs.insert((token.line, token.column), doc);
+            }
+            continue;
+        }
+        if !starts_line(index) {
+            continue;
+        }
+
+        let text = &token.lexeme["///".len()..];
+        let text = text.strip_prefix(' ').unwrap_or(text).trim_end();
+        pending = match pending {
+            Some((line, previous)) if line + 1 == token.line => {
+                Some((token.line, format!("{}\n{}", previous, text)))
+            }
+            _ => Some((token.line, text.to_string())),
+        };
+    }
+    docs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+
+    fn parse_stmts(source: &str) -> Vec<Result<Stmt>> {
+        let mut scanner = Scanner::new(source.into());
+        scanner.scan_tokens();
+        let mut parser = Parser::new(&scanner.tokens, false);
+
+        match parser.parse() {
+            ParseResult::List(stmts) => stmts,
+            ParseResult::SingleExpr(_) | ParseResult::Incomplete => unreachable!(),
+        }
+    }
+
+    fn parse_expr(source: &str) -> Expr {
+        match parse_stmts(source).remove(0) {
+            Ok(Stmt::Expression(expr)) => expr,
+            x => panic!("expected expression statement, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn xor_precedence() {
+        match parse_expr("a or b xor c == d and e;") {
+            Expr::LogicOr(_, right) => match *right {
+                Expr::LogicXor(left, right) => {
+                    assert!(matches!(*left, Expr::Variable(..)));
+                    assert!(matches!(*right, Expr::LogicAnd(ref left, _)
+                        if matches!(**left, Expr::Binary(..))));
+                }
+                x => panic!("expected xor expression, found {:?}", x),
+            },
+            x => panic!("expected or expression, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn print_several_values() {
+        match parse_stmts("print 1, a + 2;").remove(0) {
+            Ok(Stmt::Print(values)) => {
+                assert!(matches!(values[..], [Expr::Number(_), Expr::Binary(..)]));
+            }
+            x => panic!("expected print statement, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn property_access() {
+        let expr = parse_expr("a.b;");
+
+        match expr {
+            Expr::Get(object, name) => {
+                assert!(matches!(*object, Expr::Variable(ref token, _) if token.lexeme == "a"));
+                assert_eq!(name.lexeme, "b");
+            }
+            x => panic!("expected get expression, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn chained_property_access() {
+        let expr = parse_expr("a.b.c;");
+
+        match expr {
+            Expr::Get(object, name) => {
+                assert_eq!(name.lexeme, "c");
+                assert!(matches!(*object, Expr::Get(_, ref token) if token.lexeme == "b"));
+            }
+            x => panic!("expected get expression, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn property_access_after_call() {
+        let expr = parse_expr("a.b().c;");
+
+        match expr {
+            Expr::Get(object, name) => {
+                assert_eq!(name.lexeme, "c");
+                match *object {
+                    Expr::Call(callee, _, arguments) => {
+                        assert!(arguments.is_empty());
+                        assert!(matches!(*callee, Expr::Get(_, ref token) if token.lexeme == "b"));
+                    }
+                    x => panic!("expected call expression, found {:?}", x),
+                }
+            }
+            x => panic!("expected get expression, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn property_assignment() {
+        let expr = parse_expr("a.b = 2;");
+
+        match expr {
+            Expr::Set(object, name, value) => {
+                assert!(matches!(*object, Expr::Variable(ref token, _) if token.lexeme == "a"));
+                assert_eq!(name.lexeme, "b");
+                assert!(matches!(*value, Expr::Number(x) if x == 2.0));
+            }
+            x => panic!("expected set expression, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn chained_property_assignment() {
+        let expr = parse_expr("a.b.c = 3;");
+
+        match expr {
+            Expr::Set(object, name, value) => {
+                assert!(matches!(*object, Expr::Get(_, ref token) if token.lexeme == "b"));
+                assert_eq!(name.lexeme, "c");
+                assert!(matches!(*value, Expr::Number(x) if x == 3.0));
+            }
+            x => panic!("expected set expression, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn invalid_assignment_target() {
+        let stmts = parse_stmts("foo() = 3;");
+
+        assert_eq!(stmts.len(), 1);
+        assert_eq!(
+            stmts[0].as_ref().unwrap_err(),
+            &LoxError::ParserError(1, "Invalid assignment target".to_string())
+        );
+    }
+
+    #[test]
+    fn empty_class_declaration() {
+        let mut stmts = parse_stmts("class Foo {}");
+
+        match stmts.remove(0) {
+            Ok(Stmt::Class {
+                token,
+                superclass,
+                methods,
+            }) => {
+                assert_eq!(token.lexeme, "Foo");
+                assert!(superclass.is_none());
+                assert!(methods.is_empty());
+            }
+            x => panic!("expected class declaration, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn class_declaration_with_methods() {
+        let mut stmts = parse_stmts(
+            r#"class Foo {
+                bar() { return 1; }
+                baz(a, b) { print a + b; }
+            }"#,
+        );
+
+        match stmts.remove(0) {
+            Ok(Stmt::Class { token, methods, .. }) => {
+                assert_eq!(token.lexeme, "Foo");
+                let names: Vec<&str> = methods.iter().map(|m| m.0.lexeme.as_str()).collect();
+                assert_eq!(names, vec!["bar", "baz"]);
+                assert_eq!(methods[1].1.len(), 2);
+            }
+            x => panic!("expected class declaration, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn missing_semicolon_error() {
+        let stmts = parse_stmts("print 1");
+
+        match stmts[0].as_ref().unwrap_err() {
+            LoxError::ExpectedToken {
+                expected, found, ..
+            } => {
+                assert_eq!(expected, &TokenType::Semicolon);
+                assert_eq!(found.kind, TokenType::Eof);
+            }
+            x => panic!("expected missing token error, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn missing_expression_error() {
+        let stmts = parse_stmts("print ;");
+
+        match stmts[0].as_ref().unwrap_err() {
+            LoxError::ExpectedExpression(found) => assert_eq!(found.kind, TokenType::Semicolon),
+            x => panic!("expected missing expression error, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn this_inside_method() {
+        let mut stmts = parse_stmts("class Foo { bar() { return this.baz; } }");
+
+        let methods = match stmts.remove(0) {
+            Ok(Stmt::Class { methods, .. }) => methods,
+            x => panic!("expected class declaration, found {:?}", x),
+        };
+
+        match &methods[0].2[0] {
+            Stmt::Return(_, Some(Expr::Get(object, name))) => {
+                assert!(matches!(**object, Expr::This(ref token, _) if token.lexeme == "this"));
+                assert_eq!(name.lexeme, "baz");
+            }
+            x => panic!("expected return of this property, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn malformed_argument_list_suggestion() {
+        let stmts = parse_stmts("foo(1, 2 };");
+
+        match stmts[0].as_ref().unwrap_err() {
+            LoxError::ExpectedToken { message, .. } => {
+                assert_eq!(message, "Expected ')' or ',', found '}'.")
+            }
+            x => panic!("expected missing token error, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn lenient_missing_semicolon() {
+        let mut scanner = Scanner::new("var a = 1\nprint a;".into());
+        scanner.scan_tokens();
+        let mut parser = Parser::new(&scanner.tokens, false).with_lenient_semicolons(true);
+
+        let stmts = match parser.parse() {
+            ParseResult::List(stmts) => stmts,
+            ParseResult::SingleExpr(_) | ParseResult::Incomplete => unreachable!(),
+        };
+
+        assert_eq!(stmts.len(), 2);
+        assert!(
+            matches!(stmts[0], Ok(Stmt::Var(ref token, _, Some(Expr::Number(_)))) if token.lexeme == "a")
+        );
+        assert!(
+            matches!(stmts[1], Ok(Stmt::Print(ref values)) if matches!(values[..], [Expr::Variable(..)]))
+        );
+        assert_eq!(parser.warnings().len(), 1);
+    }
+
+    #[test]
+    fn strict_missing_semicolon() {
+        let stmts = parse_stmts("var a = 1\nprint a;");
+
+        assert!(stmts[0].is_err());
+    }
+
+    #[test]
+    fn truncated_expression_is_parse_error() {
+        let tokens = vec![
+            Token::new(TokenType::Number(1.0), "1".to_string(), 3),
+            Token::new(TokenType::Plus, "+".to_string(), 3),
+        ];
+        let mut parser = Parser::new(&tokens, false);
+
+        match parser.parse() {
+            ParseResult::List(stmts) => assert!(matches!(
+                stmts.as_slice(),
+                [Err(LoxError::ParserError(3, _))]
+            )),
+            ParseResult::SingleExpr(_) | ParseResult::Incomplete => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn truncated_grouping_is_parse_error() {
+        let tokens = vec![
+            Token::new(TokenType::LeftParen, "(".to_string(), 1),
+            Token::new(TokenType::Number(1.0), "1".to_string(), 1),
+        ];
+        let mut parser = Parser::new(&tokens, false);
+
+        match parser.parse() {
+            ParseResult::List(stmts) => assert!(matches!(
+                stmts.as_slice(),
+                [Err(LoxError::ParserError(1, _))]
+            )),
+            ParseResult::SingleExpr(_) | ParseResult::Incomplete => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn pipeline_desugars_to_calls() {
+        let expr = parse_expr("3 |> double |> inc;");
+
+        assert_eq!(
+            crate::ast_printer::ASTPrinter::print(&expr),
+            "(call inc (call double 3))"
+        );
+    }
+
+    #[test]
+    fn compound_assignment_desugars() {
+        let printed = ["x += 1;", "x -= 2;", "x *= 3;", "x /= 4;", "a.b += c;"]
+            .map(|source| crate::ast_printer::ASTPrinter::print(&parse_expr(source)));
+
+        assert_eq!(
+            printed,
+            [
+                "(= x (+ x 1))",
+                "(= x (- x 2))",
+                "(= x (* x 3))",
+                "(= x (/ x 4))",
+                "(+= (. a b) c)"
+            ]
+        );
+        assert!(parse_stmts("1 += 2;")[0].is_err());
+    }
+
+    #[test]
+    fn doc_comment_attached_to_function() {
+        let stmts = parse_stmts("/// Adds\n/// two numbers\nfun add(a, b) { return a + /// ignored\n b; }\n\nfun other() {}");
+
+        match (&stmts[0], &stmts[1]) {
+            (Ok(Stmt::Function(_, _, _, add)), Ok(Stmt::Function(_, _, _, other))) => {
+                assert_eq!(add.doc.as_deref(), Some("Adds\ntwo numbers"));
+                assert_eq!(other.doc, None);
+            }
+            x => panic!("expected two functions, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn doc_comment_attached_across_blank_lines() {
+        let stmts = parse_stmts(
+            "/// Old\n\n/// Greets\n\nfun\ngreet() {}\nclass A {\n  /// Beeps\n\n  beep() {}\n}",
+        );
+
+        match &stmts[0] {
+            Ok(Stmt::Function(_, _, _, greet)) => {
+                assert_eq!(greet.doc.as_deref(), Some("Greets"));
+            }
+            x => panic!("expected function, found {:?}", x),
+        }
+        match &stmts[1] {
+            Ok(Stmt::Class { methods, .. }) => {
+                assert_eq!(methods[0].3.doc.as_deref(), Some("Beeps"));
+            }
+            x => panic!("expected class, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn switch_statement() {
+        let stmts =
+            parse_stmts("switch (x) { case 1: print 1; print 2; case 2: default: print 3; }");
+
+        match &stmts[0] {
+            Ok(Stmt::Switch(_, cases, Some(default))) => {
+                let sizes: Vec<usize> = cases.iter().map(|(_, body)| body.len()).collect();
+                assert_eq!(sizes, [2, 0]);
+                assert_eq!(default.len(), 1);
+            }
+            x => panic!("expected switch, found {:?}", x),
+        }
+        assert!(parse_stmts("switch (x) { default: default: }")[0].is_err());
+    }
+
+    #[test]
+    fn var_type_annotation() {
+        let stmts = parse_stmts("var x: Number = 1;");
+
+        match &stmts[0] {
+            Ok(Stmt::Var(name, Some(annotation), Some(_))) => {
+                assert_eq!(name.lexeme, "x");
+                assert_eq!(annotation.lexeme, "Number");
+            }
+            x => panic!("expected annotated variable, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn function_type_annotations() {
+        let stmts = parse_stmts("fun f(a: Number, b) -> String { return a + b; }");
+
+        match &stmts[0] {
+            Ok(Stmt::Function(_, params, _, signature)) => {
+                assert_eq!(params.len(), 2);
+                let types: Vec<Option<&str>> = signature
+                    .params
+                    .iter()
+                    .map(|param| param.as_ref().map(|token| token.lexeme.as_str()))
+                    .collect();
+                assert_eq!(types, vec![Some("Number"), None]);
+                assert_eq!(signature.returns.as_deref().unwrap().lexeme, "String");
+            }
+            x => panic!("expected annotated function, found {:?}", x),
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use crate::error::{LoxError, Result};
+use crate::interpreter::Interpreter;
+use crate::lox_callable::Callable;
+use crate::object::Object;
+use crate::token::Token;
+
+type StringMethod = fn(&str, &[Object]) -> Result<Object>;
+type NumberMethod = fn(f64, &[Object]) -> Result<Object>;
+
+const STRING_METHODS: &[(&str, usize, StringMethod)] = &[
+    ("upper", 0, |value, _| {
+        Ok(Object::String(value.to_uppercase()))
+    }),
+    ("lower", 0, |value, _| {
+        Ok(Object::String(value.to_lowercase()))
+    }),
+    ("trim", 0, |value, _| {
+        Ok(Object::String(value.trim().to_string()))
+    }),
+    ("length", 0, |value, _| {
+        Ok(Object::Number(value.chars().count() as f64))
+    }),
+];
+
+const NUMBER_METHODS: &[(&str, usize, NumberMethod)] = &[
+    ("floor", 0, |value, _| Ok(Object::Number(value.floor()))),
+    ("ceil", 0, |value, _| Ok(Object::Number(value.ceil()))),
+    ("round", 0, |value, _| Ok(Object::Number(value.round()))),
+    ("abs", 0, |value, _| Ok(Object::Number(value.abs()))),
+];
+
+#[derive(Clone, Debug)]
+enum Receiver {
+    String(String, StringMethod),
+    Number(f64, NumberMethod),
+}
+
+// Built-in method of a string or number, already bound to the value it was looked up on
+#[derive(Clone, Debug)]
+pub struct PrimitiveMethod {
+    receiver: Receiver,
+    arity: usize,
+}
+
+impl PrimitiveMethod {
+    pub fn get(object: &Object, property: &Token) -> Result<Object> {
+        let name = property.lexeme.as_str();
+        let method = match object {
+            Object::String(value) => STRING_METHODS
+                .iter()
+                .find(|(method, _, _)| *method == name)
+                .map(|&(_, arity, function)| PrimitiveMethod {
+                    receiver: Receiver::String(value.clone(), function),
+                    arity,
+                }),
+            Object::Number(value) => NUMBER_METHODS
+                .iter()
+                .find(|(method, _, _)| *method == name)
+                .map(|&(_, arity, function)| PrimitiveMethod {
+                    receiver: Receiver::Number(*value, function),
+                    arity,
+                }),
+            _ => None,
+        };
+
+        method
+            .map(|method| Object::Call(Box::new(method)))
+            .ok_or_else(|| {
+                LoxError::RuntimeError(
+                    property.clone(),
+                    format!("Undefined property '{}'", property.lexeme),
+                )
+            })
+    }
+}
+
+impl Callable for PrimitiveMethod {
+    fn arity(&self) -> usize {
+        self.arity
+    }
+
+    fn call(&self, arguments: &[Object], _: &mut Interpreter) -> Result<Object> {
+        match &self.receiver {
+            Receiver::String(value, function) => function(value, arguments),
+            Receiver::Number(value, function) => function(*value, arguments),
+        }
+    }
+}
@@ -1,11 +1,12 @@
 use super::expr;
 use super::expr::Expr;
+use super::lox;
 use super::stmt;
-use super::stmt::{Function, Stmt};
+use super::stmt::{Case, Function, Signature, Stmt};
 use super::token::Token;
 use crate::error::{LoxError, Result};
 use crate::token_type::TokenType;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 #[derive(PartialEq, Debug)]
 pub enum VarState {
@@ -23,6 +24,14 @@ impl VarState {
         matches!(self, VarState::Read { .. })
     }
 
+    fn name(&self) -> &'static str {
+        match self {
+            VarState::Declared { .. } => "declared",
+            VarState::Defined { .. } => "defined",
+            VarState::Read { .. } => "read",
+        }
+    }
+
     fn token(&self) -> &Token {
         match self {
             VarState::Declared { token, .. } => token,
@@ -48,38 +57,214 @@ enum FunctionType {
     Method,
 }
 
+#[derive(Copy, Clone, PartialEq)]
+enum ClassType {
+    None,
+    Class,
+    Subclass,
+}
+
 pub struct Resolver {
     scopes: Vec<HashMap<String, VarState>>,
     expr_id_scope_depth: HashMap<u64, u64>,
     current_function: FunctionType,
+    current_class: ClassType,
+    loop_depth: usize,
+    warnings: Vec<String>,
+    warn_condition_assignment: bool, // warns about `if (x = 5)`, usually a mistyped `==`
+    warnings_as_errors: bool,
+    strict_assignments: bool, // assigning to a name no scope declares is an error
+    known_globals: HashSet<String>,
+    program_globals: HashSet<String>, // declared anywhere at the top level of the program
+    late_reads: HashSet<String>,      // globals read before their last declaration
+    readonly: HashSet<String>,        // host globals that can't be assigned or shadowed
+    unused_check: bool,               // unused variables are errors
+    unused_locals: Vec<Token>,        // gathered as their scopes close
+    debug: bool,                      // records how scopes and variables are resolved
+    debug_log: Vec<String>,
+}
+impl Default for Resolver {
+    fn default() -> Self {
+        Self::new()
+    }
 }
+
 impl Resolver {
     pub fn new() -> Self {
         Resolver {
             scopes: vec![HashMap::new()],
             expr_id_scope_depth: HashMap::new(),
             current_function: FunctionType::None,
+            current_class: ClassType::None,
+            loop_depth: 0,
+            warnings: vec![],
+            warn_condition_assignment: false,
+            warnings_as_errors: false,
+            strict_assignments: false,
+            known_globals: HashSet::new(),
+            program_globals: HashSet::new(),
+            late_reads: HashSet::new(),
+            readonly: HashSet::new(),
+            unused_check: true,
+            unused_locals: vec![],
+            debug: false,
+            debug_log: vec![],
         }
     }
-    pub fn run(mut self, statements: &[Stmt]) -> Result<HashMap<u64, u64>> {
-        self.resolve_stmts(statements)?;
 
-        let unused_variable = self
+    pub fn with_condition_assignment_warning(mut self, enabled: bool) -> Self {
+        self.warn_condition_assignment = enabled;
+        self
+    }
+
+    // The REPL may assign names declared on earlier lines, which a fresh resolver never saw,
+    // so this is only enabled when running files
+    pub fn with_strict_assignments(mut self, enabled: bool) -> Self {
+        self.strict_assignments = enabled;
+        self
+    }
+
+    // Globals defined by the host, like natives, which are never declared in Lox code
+    pub fn with_globals(mut self, names: impl IntoIterator<Item = String>) -> Self {
+        self.known_globals.extend(names);
+        self
+    }
+
+    pub fn with_readonly(mut self, names: impl IntoIterator<Item = String>) -> Self {
+        self.readonly.extend(names);
+        self
+    }
+
+    // Each REPL line is resolved on its own, so a variable is usually used on a later line
+    pub fn with_unused_check(mut self, enabled: bool) -> Self {
+        self.unused_check = enabled;
+        self
+    }
+
+    pub fn with_warnings_as_errors(mut self, enabled: bool) -> Self {
+        self.warnings_as_errors = enabled;
+        self
+    }
+
+    // Names already defined in the outermost scope, such as variables supplied by the host.
+    // They're never reported as unused.
+    pub fn with_bindings<'a>(mut self, names: impl IntoIterator<Item = &'a String>) -> Self {
+        for name in names {
+            let token = Token::new(TokenType::Identifier, name.clone(), 0);
+            self.scopes[0].insert(name.clone(), VarState::Read { token });
+        }
+        self
+    }
+
+    pub fn with_debug(mut self, debug: bool) -> Self {
+        self.debug = debug;
+        self
+    }
+    pub fn run(&mut self, statements: &[Stmt]) -> Result<HashMap<u64, u64>> {
+        self.program_globals = statements.iter().filter_map(declared_name).collect();
+        self.late_reads.clear();
+        if let Err(err) = self.resolve_stmts(statements) {
+            // a resolver kept across REPL lines must be usable again after an error
+            self.scopes.truncate(1);
+            self.current_function = FunctionType::None;
+            self.current_class = ClassType::None;
+            self.loop_depth = 0;
+            self.unused_locals.clear();
+            return Err(err);
+        }
+
+        let unused_locals = std::mem::take(&mut self.unused_locals);
+        let mut unused_variables: Vec<&Token> = self
             .scopes
             .iter()
             .flat_map(|map| map.values())
-            .filter(|var_state| !var_state.is_read())
+            .filter(|var_state| self.unused_check && !var_state.is_read())
+            .filter(|var_state| !self.late_reads.contains(&var_state.token().lexeme))
             .map(|state| state.token())
-            .take(1)
-            .next();
+            .chain(&unused_locals)
+            .collect();
+        unused_variables.sort_by_key(|token| (token.line, token.column));
 
-        if let Some(unused_token) = unused_variable {
-            return Err(LoxError::ResolverError(
-                unused_token.clone(),
-                format!("Variable '{}' declared and not used", unused_token.lexeme),
-            ));
+        let mut errors: Vec<LoxError> = unused_variables
+            .into_iter()
+            .map(|unused_token| {
+                LoxError::ResolverError(
+                    unused_token.clone(),
+                    format!("Variable '{}' declared and not used", unused_token.lexeme),
+                )
+            })
+            .collect();
+        match errors.len() {
+            0 => {}
+            1 => return Err(errors.remove(0)),
+            _ => return Err(LoxError::Multiple(errors)),
+        }
+
+        if self.debug {
+            let mut depths: Vec<(u64, u64)> = self
+                .expr_id_scope_depth
+                .iter()
+                .map(|(expr_id, depth)| (*expr_id, *depth))
+                .collect();
+            depths.sort();
+            for (expr_id, depth) in depths {
+                self.debug(format!("expr {} -> depth {}", expr_id, depth));
+            }
+        }
+        Ok(std::mem::take(&mut self.expr_id_scope_depth))
+    }
+
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    pub fn debug_log(&self) -> &[String] {
+        &self.debug_log
+    }
+
+    fn debug(&mut self, line: String) {
+        println!("[resolver] {}", line);
+        self.debug_log.push(line);
+    }
+
+    fn debug_scope(&mut self, event: &str) {
+        if !self.debug {
+            return;
+        }
+        let index = self.scopes.len() - 1;
+        let mut variables: Vec<String> = self.scopes[index]
+            .iter()
+            .map(|(name, state)| format!("{}: {}", name, state.name()))
+            .collect();
+        variables.sort();
+        self.debug(format!(
+            "{} scope {} {{{}}}",
+            event,
+            index,
+            variables.join(", ")
+        ));
+    }
+
+    fn warning(&mut self, token: &Token, message: &str) -> Result<()> {
+        if self.warnings_as_errors {
+            return Err(LoxError::ResolverError(token.clone(), message.to_string()));
+        }
+
+        self.warnings.push(lox::warning(token, message));
+        Ok(())
+    }
+
+    fn check_condition(&mut self, cond: &Expr) -> Result<()> {
+        match cond {
+            Expr::Assign(token, _, _) if self.warn_condition_assignment => self.warning(
+                token,
+                &format!(
+                    "Assignment to '{}' used as a condition, did you mean '=='?",
+                    token.lexeme
+                ),
+            ),
+            _ => Ok(()),
         }
-        Ok(self.expr_id_scope_depth)
     }
     fn resolve_expr(&mut self, expr: &Expr) -> Result<()> {
         expr.accept(self)
@@ -90,20 +275,58 @@ impl Resolver {
     }
 
     fn resolve_stmts(&mut self, stmts: &[Stmt]) -> Result<()> {
-        stmts
-            .into_iter()
-            .map(|stmt| self.resolve_stmt(stmt))
-            .collect()
+        check_inheritance(stmts)?;
+        stmts.iter().try_for_each(|stmt| self.resolve_stmt(stmt))?;
+
+        // Only statements followed by something else can make code unreachable
+        let leading = stmts
+            .split_last()
+            .map(|(_, leading)| leading)
+            .unwrap_or(&[]);
+        // a `return` directly followed by more code is always a mistake, other cases only warn
+        if let Some(Stmt::Return(token, _)) =
+            leading.iter().find(|stmt| matches!(stmt, Stmt::Return(..)))
+        {
+            return Err(LoxError::ResolverError(
+                token.clone(),
+                "Unreachable code after return".to_string(),
+            ));
+        }
+        if let Some(token) = leading.iter().find_map(terminating_token) {
+            self.warning(
+                token,
+                &format!("Unreachable code after line {}", token.line),
+            )?;
+        }
+
+        Ok(())
     }
 
     fn begin_scope(&mut self) {
-        self.scopes.push(HashMap::new())
+        self.scopes.push(HashMap::new());
+        self.debug_scope("open");
     }
 
     fn end_scope(&mut self) {
-        self.scopes.pop();
+        self.debug_scope("close");
+        let scope = self.scopes.pop().unwrap_or_default();
+        if self.unused_check {
+            // `this` and `super` are bound implicitly, so they're never reported
+            let unused = scope
+                .into_values()
+                .filter(|var_state| !var_state.is_read())
+                .map(|var_state| var_state.token().clone())
+                .filter(|token| token.kind == TokenType::Identifier);
+            self.unused_locals.extend(unused);
+        }
     }
     fn declare(&mut self, token: &Token) -> Result<()> {
+        if self.readonly.contains(&token.lexeme) {
+            return Err(LoxError::ResolverError(
+                token.clone(),
+                format!("Can't shadow read-only variable '{}'", token.lexeme),
+            ));
+        }
         let past_value = self.scopes.iter_mut().last().and_then(|map| {
             map.insert(
                 token.lexeme.clone(),
@@ -113,18 +336,23 @@ impl Resolver {
             )
         });
 
-        // If there was some past value, it means that variable is being declared again
-        if let Some(_) = past_value {
+        // If there was some past value, it means that variable is being declared again.
+        // Like reference Lox, that is only allowed at the global scope, so the REPL can redefine.
+        if past_value.is_some() && self.scopes.len() > 1 {
             return Err(LoxError::ResolverError(
                 token.clone(),
                 format!("Variable '{}' already declared", token.lexeme),
             ));
         }
+        // a global read before being redefined is used, even if the new definition isn't read
+        if past_value.is_some_and(|past_value| past_value.is_read()) {
+            self.late_reads.insert(token.lexeme.clone());
+        }
 
         Ok(())
     }
     fn define(&mut self, token: &Token) -> Result<()> {
-        self.scopes.iter_mut().last().map(|map| {
+        if let Some(map) = self.scopes.iter_mut().last() {
             map.entry(token.lexeme.clone())
                 .and_modify(|entry| {
                     if let VarState::Declared { token } = entry {
@@ -136,7 +364,7 @@ impl Resolver {
                 .or_insert(VarState::Defined {
                     token: token.clone(),
                 });
-        });
+        }
         Ok(())
     }
 
@@ -145,11 +373,15 @@ impl Resolver {
         let found_index = self
             .scopes
             .iter()
-            .rposition(|scope| scope.get(&token.lexeme).is_some());
+            .rposition(|scope| scope.get(&token.lexeme).is_some())
+            .or_else(|| self.is_late_bound_global(&token.lexeme).then_some(0));
+        if mark_as_read && found_index == Some(0) && !self.scopes[0].contains_key(&token.lexeme) {
+            self.late_reads.insert(token.lexeme.clone());
+        }
 
         if mark_as_read {
             found_index.map(|found_index| {
-                self.scopes.iter_mut().nth(found_index).map(|scope_map| {
+                self.scopes.get_mut(found_index).map(|scope_map| {
                     scope_map
                         .entry(token.lexeme.clone())
                         .and_modify(VarState::set_has_been_read)
@@ -158,10 +390,21 @@ impl Resolver {
         };
 
         if let Some(found_index) = found_index {
-            self.expr_id_scope_depth
-                .insert(expr_id, scope_size - 1 - (found_index as u64));
+            let depth = scope_size - 1 - (found_index as u64);
+            self.expr_id_scope_depth.insert(expr_id, depth);
+            if self.debug {
+                self.debug(format!(
+                    "'{}' (expr {}) at depth {}",
+                    token.lexeme, expr_id, depth
+                ));
+            }
         }
     }
+    // Globals are late bound, so a function body may use one declared further down the program
+    fn is_late_bound_global(&self, name: &str) -> bool {
+        self.current_function != FunctionType::None && self.program_globals.contains(name)
+    }
+
     fn resolve_function(
         &mut self,
         params: &[Token],
@@ -169,20 +412,115 @@ impl Resolver {
         kind: FunctionType,
     ) -> Result<()> {
         let enclosing_function = self.current_function;
+        let enclosing_loop_depth = self.loop_depth;
         self.current_function = kind;
+        self.loop_depth = 0; // loops outside of the function can't be broken from inside it
         self.begin_scope();
 
         params
-            .into_iter()
-            .map(|param| self.declare(param).and(self.define(param)))
-            .collect::<Result<()>>()?;
+            .iter()
+            .try_for_each(|param| self.declare(param).and(self.define(param)))?;
+        // a callback often ignores some of its parameters, so they're never reported as unused
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.values_mut().for_each(VarState::set_has_been_read);
+        }
         self.resolve_stmts(body)?;
         self.end_scope();
 
         self.current_function = enclosing_function;
+        self.loop_depth = enclosing_loop_depth;
         Ok(())
     }
 }
+// Token of the statement that makes every path through `stmt` leave the enclosing block,
+// or None when execution can continue after it.
+fn terminating_token(stmt: &Stmt) -> Option<&Token> {
+    match stmt {
+        Stmt::Return(token, _) | Stmt::Break(token) | Stmt::Continue(token) => Some(token),
+        Stmt::Block(statements) => statements.iter().find_map(terminating_token),
+        Stmt::If(_, then_branch, Some(else_branch)) => {
+            terminating_token(then_branch).and(terminating_token(else_branch))
+        }
+        _ => None,
+    }
+}
+
+// Superclasses must already exist when a class is declared, so among the classes of one
+// scope a superclass declared further down is either a cycle or a forward reference.
+// Inheriting from itself is left to `visit_class_stmt`.
+fn check_inheritance(stmts: &[Stmt]) -> Result<()> {
+    let superclasses: HashMap<&str, &Token> = stmts.iter().filter_map(superclass_of).collect();
+    let classes: HashSet<&str> = stmts
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Class { token, .. } => Some(token.lexeme.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    let mut declared = HashSet::new();
+    for stmt in stmts {
+        if let Stmt::Class { token, .. } = stmt {
+            declared.insert(token.lexeme.as_str());
+        }
+        let (class, superclass) = match superclass_of(stmt) {
+            Some(pair) => pair,
+            None => continue,
+        };
+
+        let mut chain = vec![class];
+        let mut current = superclass;
+        while !chain.contains(&current.lexeme.as_str()) {
+            chain.push(&current.lexeme);
+            match superclasses.get(current.lexeme.as_str()) {
+                Some(next) => current = next,
+                None => break,
+            }
+        }
+        if current.lexeme == class {
+            chain.push(class);
+            return Err(LoxError::ResolverError(
+                superclass.clone(),
+                format!("Inheritance cycle between classes {}", chain.join(" < ")),
+            ));
+        }
+
+        if classes.contains(superclass.lexeme.as_str())
+            && !declared.contains(superclass.lexeme.as_str())
+        {
+            return Err(LoxError::ResolverError(
+                superclass.clone(),
+                format!(
+                    "Class '{}' inherits from '{}', which is declared after it",
+                    class, superclass.lexeme
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn declared_name(stmt: &Stmt) -> Option<String> {
+    match stmt {
+        Stmt::Var(token, _, _) | Stmt::Function(token, ..) | Stmt::Class { token, .. } => {
+            Some(token.lexeme.clone())
+        }
+        _ => None,
+    }
+}
+
+fn superclass_of(stmt: &Stmt) -> Option<(&str, &Token)> {
+    match stmt {
+        Stmt::Class {
+            token,
+            superclass: Some(Expr::Variable(superclass, _)),
+            ..
+        } if token.lexeme != superclass.lexeme => Some((token.lexeme.as_str(), superclass)),
+        _ => None,
+    }
+}
+
 impl stmt::Visitor<Result<()>> for Resolver {
     fn visit_block_stmt(&mut self, statements: &[stmt::Stmt]) -> Result<()> {
         self.begin_scope();
@@ -195,13 +533,14 @@ impl stmt::Visitor<Result<()>> for Resolver {
         self.resolve_expr(expr)
     }
 
-    fn visit_print_stmt(&mut self, expr: &expr::Expr) -> Result<()> {
-        self.resolve_expr(expr)
+    fn visit_print_stmt(&mut self, values: &[expr::Expr]) -> Result<()> {
+        values.iter().try_for_each(|value| self.resolve_expr(value))
     }
 
     fn visit_var_stmt(
         &mut self,
         token: &crate::token::Token,
+        _: Option<&Token>,
         expr: Option<&expr::Expr>,
     ) -> Result<()> {
         self.declare(token)?;
@@ -218,6 +557,7 @@ impl stmt::Visitor<Result<()>> for Resolver {
         then_branch: &stmt::Stmt,
         else_branch: Option<&stmt::Stmt>,
     ) -> Result<()> {
+        self.check_condition(cond)?;
         self.resolve_expr(cond)?;
         self.resolve_stmt(then_branch)?;
 
@@ -228,9 +568,47 @@ impl stmt::Visitor<Result<()>> for Resolver {
         Ok(())
     }
 
-    fn visit_while_stmt(&mut self, cond: &expr::Expr, block: &stmt::Stmt) -> Result<()> {
+    fn visit_while_stmt(
+        &mut self,
+        cond: &expr::Expr,
+        block: &stmt::Stmt,
+        increment: Option<&expr::Expr>,
+        else_branch: Option<&stmt::Stmt>,
+    ) -> Result<()> {
+        self.check_condition(cond)?;
         self.resolve_expr(cond)?;
-        self.resolve_stmt(block)
+        let enclosing_loop_depth = self.loop_depth;
+        self.loop_depth += 1;
+        let result = self.resolve_stmt(block);
+        self.loop_depth = enclosing_loop_depth;
+        result?;
+
+        if let Some(increment) = increment {
+            self.resolve_expr(increment)?;
+        }
+
+        if let Some(else_branch) = else_branch {
+            self.resolve_stmt(else_branch)?;
+        }
+
+        Ok(())
+    }
+
+    fn visit_switch_stmt(
+        &mut self,
+        value: &expr::Expr,
+        cases: &[Case],
+        default: Option<&[stmt::Stmt]>,
+    ) -> Result<()> {
+        self.resolve_expr(value)?;
+        for (case, body) in cases {
+            self.resolve_expr(case)?;
+            self.visit_block_stmt(body)?;
+        }
+        if let Some(default) = default {
+            self.visit_block_stmt(default)?;
+        }
+        Ok(())
     }
 
     fn visit_function_stmt(
@@ -238,6 +616,7 @@ impl stmt::Visitor<Result<()>> for Resolver {
         token: &crate::token::Token,
         params: &[crate::token::Token],
         body: &[stmt::Stmt],
+        _: &Signature,
     ) -> Result<()> {
         self.declare(token)?;
         self.define(token)?;
@@ -245,43 +624,104 @@ impl stmt::Visitor<Result<()>> for Resolver {
         Ok(())
     }
 
-    fn visit_return_stmt(&mut self, token: &Token, expr: &expr::Expr) -> Result<()> {
+    fn visit_return_stmt(&mut self, token: &Token, expr: Option<&expr::Expr>) -> Result<()> {
         if self.current_function == FunctionType::None {
             return Err(LoxError::ResolverError(
                 token.clone(),
                 "Can't return on top-level code".to_string(),
             ));
         }
-        self.resolve_expr(expr)
+        if self.current_function == FunctionType::Initializer && expr.is_some() {
+            return Err(LoxError::ResolverError(
+                token.clone(),
+                "Can't return a value from an initializer".to_string(),
+            ));
+        }
+        expr.map_or(Ok(()), |expr| self.resolve_expr(expr))
     }
 
-    fn visit_class_stmt(&mut self, token: &Token, methods: &[Function]) -> Result<()> {
-        self.declare(token).and(self.define(token)).and(
-            methods
-                .into_iter()
-                .map(|(token, parameters, body)| {
-                    self.begin_scope();
-                    self.scopes.last_mut().map(|scope| {
-                        scope.insert(
-                            "this".to_string(),
-                            VarState::Defined {
-                                token: Token::new(TokenType::This, "this".to_string(), 0),
-                            },
-                        )
-                    });
-                    let function_type = (token.lexeme == "init")
-                        .then(|| FunctionType::Initializer)
-                        .unwrap_or(FunctionType::Method);
-                    let result = self.resolve_function(
-                        parameters.as_slice(),
-                        body.as_slice(),
-                        function_type,
-                    );
-                    self.end_scope();
-                    result
-                })
-                .collect::<Result<()>>(),
-        )
+    fn visit_break_stmt(&mut self, token: &Token) -> Result<()> {
+        if self.loop_depth == 0 {
+            return Err(LoxError::ResolverError(
+                token.clone(),
+                "Can't break outside of a loop".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn visit_continue_stmt(&mut self, token: &Token) -> Result<()> {
+        if self.loop_depth == 0 {
+            return Err(LoxError::ResolverError(
+                token.clone(),
+                "Can't continue outside of a loop".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn visit_class_stmt(
+        &mut self,
+        token: &Token,
+        superclass: Option<&Expr>,
+        methods: &[Function],
+    ) -> Result<()> {
+        if let Some(Expr::Variable(superclass_token, _)) = superclass {
+            if superclass_token.lexeme == token.lexeme {
+                return Err(LoxError::ResolverError(
+                    superclass_token.clone(),
+                    "A class can't inherit from itself".to_string(),
+                ));
+            }
+        }
+
+        self.declare(token)?;
+        self.define(token)?;
+
+        let enclosing_class = self.current_class;
+        self.current_class = ClassType::Class;
+
+        if let Some(superclass) = superclass {
+            self.current_class = ClassType::Subclass;
+            self.resolve_expr(superclass)?;
+            self.begin_scope();
+            self.scopes.last_mut().map(|scope| {
+                scope.insert(
+                    "super".to_string(),
+                    VarState::Defined {
+                        token: Token::new(TokenType::Super, "super".to_string(), 0),
+                    },
+                )
+            });
+        }
+
+        let result = methods.iter().try_for_each(|(token, parameters, body, _)| {
+            self.begin_scope();
+            self.scopes.last_mut().map(|scope| {
+                scope.insert(
+                    "this".to_string(),
+                    VarState::Defined {
+                        token: Token::new(TokenType::This, "this".to_string(), 0),
+                    },
+                )
+            });
+            let function_type = if token.lexeme == "init" {
+                FunctionType::Initializer
+            } else {
+                FunctionType::Method
+            };
+            let result =
+                self.resolve_function(parameters.as_slice(), body.as_slice(), function_type);
+            self.end_scope();
+            result
+        });
+
+        if superclass.is_some() {
+            self.end_scope();
+        }
+        self.current_class = enclosing_class;
+
+        result
     }
 }
 impl expr::Visitor<Result<()>> for Resolver {
@@ -310,7 +750,7 @@ impl expr::Visitor<Result<()>> for Resolver {
         args: &[expr::Expr],
     ) -> Result<()> {
         self.resolve_expr(callee)?;
-        args.into_iter().map(|arg| self.resolve_expr(arg)).collect()
+        args.iter().try_for_each(|arg| self.resolve_expr(arg))
     }
 
     fn visit_conditional_expr(
@@ -365,6 +805,28 @@ impl expr::Visitor<Result<()>> for Resolver {
         id: u64,
     ) -> Result<()> {
         self.resolve_expr(expr)?;
+
+        // shadowing is rejected, so the name always refers to the read-only global
+        if self.readonly.contains(&token.lexeme) {
+            return Err(LoxError::ResolverError(
+                token.clone(),
+                format!("Can't assign to read-only variable '{}'", token.lexeme),
+            ));
+        }
+
+        let declared = self
+            .scopes
+            .iter()
+            .any(|scope| scope.contains_key(&token.lexeme))
+            || self.known_globals.contains(&token.lexeme)
+            || self.is_late_bound_global(&token.lexeme);
+        if self.strict_assignments && !declared {
+            return Err(LoxError::ResolverError(
+                token.clone(),
+                format!("Assignment to undeclared variable '{}'", token.lexeme),
+            ));
+        }
+
         self.resolve_local(token, id, false);
         Ok(())
     }
@@ -374,6 +836,11 @@ impl expr::Visitor<Result<()>> for Resolver {
         self.resolve_expr(right)
     }
 
+    fn visit_logic_xor(&mut self, left: &expr::Expr, right: &expr::Expr) -> Result<()> {
+        self.resolve_expr(left)?;
+        self.resolve_expr(right)
+    }
+
     fn visit_logic_and(&mut self, left: &expr::Expr, right: &expr::Expr) -> Result<()> {
         self.resolve_expr(left)?;
         self.resolve_expr(right)
@@ -387,8 +854,21 @@ impl expr::Visitor<Result<()>> for Resolver {
         self.resolve_expr(object).and(self.resolve_expr(value))
     }
 
+    fn visit_compound_set_expr(
+        &mut self,
+        object: &Expr,
+        _property: &Token,
+        _operator: &Token,
+        value: &Expr,
+    ) -> Result<()> {
+        self.resolve_expr(object).and(self.resolve_expr(value))
+    }
+
     fn visit_this_expr(&mut self, token: &Token, id: u64) -> Result<()> {
-        if self.current_function != FunctionType::Method {
+        if !matches!(
+            self.current_function,
+            FunctionType::Method | FunctionType::Initializer
+        ) {
             return Err(LoxError::ResolverError(
                 token.clone(),
                 "Can't use 'this' outside of class methods".to_string(),
@@ -397,4 +877,405 @@ impl expr::Visitor<Result<()>> for Resolver {
         self.resolve_local(token, id, false);
         Ok(())
     }
+
+    fn visit_lambda_expr(&mut self, params: &[Token], body: &[Stmt]) -> Result<()> {
+        self.resolve_function(params, body, FunctionType::Function)
+    }
+
+    fn visit_map_expr(&mut self, _brace: &Token, entries: &[(Expr, Expr)]) -> Result<()> {
+        entries
+            .iter()
+            .try_for_each(|(key, value)| self.resolve_expr(key).and(self.resolve_expr(value)))
+    }
+
+    fn visit_index_expr(&mut self, object: &Expr, _bracket: &Token, index: &Expr) -> Result<()> {
+        self.resolve_expr(object).and(self.resolve_expr(index))
+    }
+
+    fn visit_index_set_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> Result<()> {
+        self.resolve_expr(object)
+            .and(self.resolve_expr(index))
+            .and(self.resolve_expr(value))
+    }
+
+    fn visit_super_expr(&mut self, keyword: &Token, _method: &Token, id: u64) -> Result<()> {
+        match self.current_class {
+            ClassType::None => Err(LoxError::ResolverError(
+                keyword.clone(),
+                "Can't use 'super' outside of a class".to_string(),
+            )),
+            ClassType::Class => Err(LoxError::ResolverError(
+                keyword.clone(),
+                "Can't use 'super' in a class with no superclass".to_string(),
+            )),
+            ClassType::Subclass => {
+                self.resolve_local(keyword, id, false);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lox;
+
+    fn resolve(source: &str) -> Result<HashMap<u64, u64>> {
+        let stmts = lox::run(source.to_string());
+        Resolver::new().run(&stmts)
+    }
+
+    #[test]
+    fn reports_every_unused_variable() {
+        let result = resolve("var a = 1;\nvar b = 2;\nvar c = 3;\nvar d = 4;\nprint c;");
+
+        match result {
+            Err(LoxError::Multiple(errors)) => {
+                let names: Vec<String> = errors
+                    .iter()
+                    .map(|error| match error {
+                        LoxError::ResolverError(token, _) => token.lexeme.clone(),
+                        x => panic!("expected resolver error, found {:?}", x),
+                    })
+                    .collect();
+                assert_eq!(names, vec!["a", "b", "d"]);
+            }
+            x => panic!("expected multiple resolver errors, found {:?}", x),
+        }
+    }
+
+    fn resolve_warning_condition_assignment(source: &str) -> Resolver {
+        let stmts = lox::run(source.to_string());
+        let mut resolver = Resolver::new().with_condition_assignment_warning(true);
+        resolver.run(&stmts).unwrap();
+        resolver
+    }
+
+    #[test]
+    fn warns_about_assignment_in_condition() {
+        let resolver = resolve_warning_condition_assignment("var x = 1; if (x = 5) print x;");
+
+        assert_eq!(
+            resolver.warnings(),
+            ["[line 1 col 16] Warning: Assignment to 'x' used as a condition, did you mean '=='?"]
+        );
+    }
+
+    #[test]
+    fn equality_in_condition_is_not_flagged() {
+        let resolver = resolve_warning_condition_assignment("var x = 1; if (x == 5) print x;");
+
+        assert!(resolver.warnings().is_empty());
+    }
+
+    #[test]
+    fn assignment_in_condition_with_warnings_as_errors() {
+        let stmts = lox::run("var x = 1; while (x = nil) print x;".to_string());
+        let result = Resolver::new()
+            .with_condition_assignment_warning(true)
+            .with_warnings_as_errors(true)
+            .run(&stmts);
+
+        assert!(matches!(result, Err(LoxError::ResolverError(_, _))));
+    }
+
+    #[test]
+    fn strict_assignment_to_undeclared_variable() {
+        let stmts = lox::run("x = 5;".to_string());
+        let result = Resolver::new().with_strict_assignments(true).run(&stmts);
+
+        match result {
+            Err(LoxError::ResolverError(token, reason)) => {
+                assert_eq!(token.lexeme, "x");
+                assert_eq!(reason, "Assignment to undeclared variable 'x'");
+            }
+            x => panic!("expected resolver error, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn strict_assignment_to_known_global() {
+        let stmts = lox::run("clock = 5;".to_string());
+        let result = Resolver::new()
+            .with_strict_assignments(true)
+            .with_globals(vec!["clock".to_string()])
+            .run(&stmts);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn strict_assignment_in_function_to_later_global() {
+        let stmts = lox::run("fun setX() { x = 5; } var x = 1; setX(); print x;".to_string());
+        let result = Resolver::new().with_strict_assignments(true).run(&stmts);
+        assert!(result.is_ok());
+
+        // top-level code runs in order, so there the declaration must come first
+        let stmts = lox::run("x = 5; var x = 1; print x;".to_string());
+        let result = Resolver::new().with_strict_assignments(true).run(&stmts);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn lenient_assignment_to_undeclared_variable() {
+        assert!(resolve("x = 5;").is_ok());
+    }
+
+    #[test]
+    fn this_outside_of_class() {
+        let result = resolve("print this;");
+
+        match result {
+            Err(LoxError::ResolverError(token, _)) => assert_eq!(token.kind, TokenType::This),
+            x => panic!("expected resolver error, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn return_value_from_initializer() {
+        let result = resolve("class Foo { init() { return 1; } }");
+
+        match result {
+            Err(LoxError::ResolverError(_, reason)) => {
+                assert_eq!(reason, "Can't return a value from an initializer")
+            }
+            x => panic!("expected resolver error, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn return_nil_from_initializer() {
+        assert_eq!(
+            resolver_error_message("class Foo { init() { return nil; } }"),
+            "Can't return a value from an initializer"
+        );
+        assert!(resolve(
+            "class Foo { init() { return; } }
+print Foo();"
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn class_inheriting_from_itself() {
+        let result = resolve("class A < A {}");
+
+        match result {
+            Err(LoxError::ResolverError(_, reason)) => {
+                assert_eq!(reason, "A class can't inherit from itself")
+            }
+            x => panic!("expected resolver error, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn super_outside_of_subclass() {
+        let result = resolve("class A { foo() { super.foo(); } }");
+
+        match result {
+            Err(LoxError::ResolverError(_, reason)) => {
+                assert_eq!(reason, "Can't use 'super' in a class with no superclass")
+            }
+            x => panic!("expected resolver error, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn break_outside_of_loop() {
+        let result = resolve("break;");
+
+        match result {
+            Err(LoxError::ResolverError(token, _)) => assert_eq!(token.kind, TokenType::Break),
+            x => panic!("expected resolver error, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn break_inside_function_inside_loop() {
+        let result = resolve("while (true) { fun f() { break; } f(); }");
+
+        assert!(matches!(result, Err(LoxError::ResolverError(..))));
+    }
+
+    #[test]
+    fn continue_outside_of_loop() {
+        let result = resolve("if (true) continue;");
+
+        match result {
+            Err(LoxError::ResolverError(token, _)) => assert_eq!(token.kind, TokenType::Continue),
+            x => panic!("expected resolver error, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn unreachable_after_exhaustive_if() {
+        let stmts = lox::run(
+            r#"
+            fun sign(x) {
+                if (x < 0) {
+                    return -1;
+                } else {
+                    return 1;
+                }
+                print "unreachable";
+            }
+            print sign(2);
+            "#
+            .to_string(),
+        );
+        let mut resolver = Resolver::new();
+
+        assert!(resolver.run(&stmts).is_ok());
+        assert_eq!(resolver.warnings().len(), 1);
+        assert!(resolver.warnings()[0].contains("Unreachable code"));
+    }
+
+    #[test]
+    fn statement_after_return_is_error() {
+        assert_eq!(
+            resolver_error_message("fun f() {\n return 1;\n print 2;\n}\nprint f();"),
+            "Unreachable code after return"
+        );
+    }
+
+    #[test]
+    fn return_as_last_statement() {
+        let result =
+            resolve("fun f(x) {\n if (x) { return 1; }\n print x;\n return 2;\n}\nprint f(true);");
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn reachable_after_partial_if() {
+        let stmts = lox::run(
+            r#"
+            fun sign(x) {
+                if (x < 0) return -1;
+                return 1;
+            }
+            print sign(2);
+            "#
+            .to_string(),
+        );
+        let mut resolver = Resolver::new();
+
+        assert!(resolver.run(&stmts).is_ok());
+        assert!(resolver.warnings().is_empty());
+    }
+
+    #[test]
+    fn debug_log_shadowed_variable_depths() {
+        let stmts = lox::run("{ var a = 1; { var a = 2; print a; } { { print a; } } }".to_string());
+        let mut resolver = Resolver::new().with_debug(true);
+        resolver.run(&stmts).unwrap();
+
+        let log = resolver.debug_log();
+        let depths: Vec<&str> = log
+            .iter()
+            .filter(|line| line.starts_with("'a'"))
+            .map(|line| line.rsplit(" at ").next().unwrap())
+            .collect();
+        assert_eq!(depths, vec!["depth 0", "depth 2"]);
+        assert!(log.contains(&"close scope 2 {a: read}".to_string()));
+        assert!(log.contains(&"open scope 1 {}".to_string()));
+    }
+
+    fn resolver_error_message(source: &str) -> String {
+        match resolve(source) {
+            Err(LoxError::ResolverError(_, message)) => message,
+            x => panic!("expected resolver error, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn rejects_inheritance_cycle() {
+        assert_eq!(
+            resolver_error_message("class A < B {} class B < A {}"),
+            "Inheritance cycle between classes A < B < A"
+        );
+    }
+
+    #[test]
+    fn rejects_inheriting_from_itself() {
+        assert_eq!(
+            resolver_error_message("class A < A {}"),
+            "A class can't inherit from itself"
+        );
+    }
+
+    #[test]
+    fn rejects_superclass_declared_later() {
+        assert_eq!(
+            resolver_error_message("class A < B {} class B {} print A;"),
+            "Class 'A' inherits from 'B', which is declared after it"
+        );
+    }
+
+    #[test]
+    fn reports_every_unused_local_variable() {
+        let result = resolve(
+            "fun f(x) { var a; var b; { var c; } }
+f(1);",
+        );
+
+        match result {
+            Err(LoxError::Multiple(errors)) => {
+                let names: Vec<String> = errors
+                    .iter()
+                    .map(|error| match error {
+                        LoxError::ResolverError(token, _) => token.lexeme.clone(),
+                        x => panic!("expected resolver error, found {:?}", x),
+                    })
+                    .collect();
+                assert_eq!(names, vec!["a", "b", "c"]);
+            }
+            x => panic!("expected multiple resolver errors, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn unused_variable_is_error_by_default() {
+        assert_eq!(
+            resolver_error_message("var x = 1;"),
+            "Variable 'x' declared and not used"
+        );
+    }
+
+    #[test]
+    fn global_redeclaration_is_allowed() {
+        assert!(resolve("var x = 1;\nprint x;\nvar x = 2;\nprint x;").is_ok());
+    }
+
+    #[test]
+    fn global_read_before_redefinition_is_used() {
+        assert!(resolve("var x = 1;\nprint x;\nvar x = 2;").is_ok());
+        assert_eq!(
+            resolver_error_message("var x = 1;\nvar x = 2;"),
+            "Variable 'x' declared and not used"
+        );
+    }
+
+    #[test]
+    fn local_redeclaration_is_error() {
+        assert_eq!(
+            resolver_error_message("{\n var x = 1;\n var x = 2;\n print x;\n}"),
+            "Variable 'x' already declared"
+        );
+    }
+
+    #[test]
+    fn unused_check_can_be_disabled() {
+        let stmts = lox::run("var x = 1;".to_string());
+        let result = Resolver::new().with_unused_check(false).run(&stmts);
+
+        assert!(result.is_ok());
+    }
 }
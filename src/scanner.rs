@@ -7,20 +7,37 @@ pub struct Scanner {
     start: usize,
     current: usize,
     line: usize,
-    source: String,
+    line_start: usize, // index where the current line begins, used for columns
+    source: Vec<char>, // indexed by char so multi-byte characters take a single position
+    keep_trivia: bool, // emit whitespace and comments as tokens instead of skipping them
 }
 
 impl Scanner {
     pub fn new(source: String) -> Scanner {
         Scanner {
-            source,
+            source: source.chars().collect(),
             tokens: vec![],
             start: 0,
             current: 0,
             line: 1,
+            line_start: 0,
+            keep_trivia: false,
         }
     }
 
+    // Scans `source` as a fragment of a larger file that begins at `line`, so tokens carry
+    // absolute line numbers. The fragment is scanned on its own: a string or block comment
+    // opened before it is not known, so a fragment must start outside of them.
+    pub fn with_start_line(mut self, line: usize) -> Self {
+        self.line = line;
+        self
+    }
+
+    pub fn with_trivia(mut self, keep_trivia: bool) -> Self {
+        self.keep_trivia = keep_trivia;
+        self
+    }
+
     #[allow(dead_code)]
     pub fn scan_tokens(&mut self) {
         while !self.is_at_end() {
@@ -28,8 +45,9 @@ impl Scanner {
             self.scan_token();
         }
 
-        self.tokens
-            .push(Token::new(TokenType::Eof, "".into(), self.line));
+        self.start = self.current;
+        let eof = Token::new(TokenType::Eof, "".into(), self.line).with_column(self.column());
+        self.tokens.push(eof);
     }
 
     fn scan_token(&mut self) {
@@ -40,12 +58,37 @@ impl Scanner {
             ')' => self.add_token(TokenType::RightParen),
             '{' => self.add_token(TokenType::LeftBrace),
             '}' => self.add_token(TokenType::RightBrace),
+            '[' => self.add_token(TokenType::LeftBracket),
+            ']' => self.add_token(TokenType::RightBracket),
             ',' => self.add_token(TokenType::Comma),
             '.' => self.add_token(TokenType::Dot),
-            '-' => self.add_token(TokenType::Minus),
-            '+' => self.add_token(TokenType::Plus),
+            '-' => {
+                let token = if self.a_match('>') {
+                    TokenType::Arrow
+                } else if self.a_match('=') {
+                    TokenType::MinusEqual
+                } else {
+                    TokenType::Minus
+                };
+                self.add_token(token);
+            }
+            '+' => {
+                let token = if self.a_match('=') {
+                    TokenType::PlusEqual
+                } else {
+                    TokenType::Plus
+                };
+                self.add_token(token);
+            }
             ';' => self.add_token(TokenType::Semicolon),
-            '*' => self.add_token(TokenType::Star),
+            '*' => {
+                let token = if self.a_match('=') {
+                    TokenType::StarEqual
+                } else {
+                    TokenType::Star
+                };
+                self.add_token(token);
+            }
             '?' => self.add_token(TokenType::Question),
             ':' => self.add_token(TokenType::Colon),
 
@@ -84,12 +127,19 @@ impl Scanner {
                 };
                 self.add_token(token);
             }
+            '|' if self.a_match('>') => self.add_token(TokenType::PipeGreater),
             '/' => {
                 if self.a_match('/') {
-                    // Line comentaries
+                    // Line comentaries, `///` but not `////` documents what follows
+                    let is_doc = self.peek() == '/' && self.peek_next() != Some('/');
                     while self.peek() != '\n' && !self.is_at_end() {
                         self.advance();
                     }
+                    if is_doc {
+                        self.add_token(TokenType::DocComment);
+                    } else {
+                        self.add_trivia(TokenType::Comment);
+                    }
                 } else if self.a_match('*') {
                     // block comentaries
                     while (self.peek() != '*' || self.peek_next() != Some('/')) && !self.is_at_end()
@@ -100,49 +150,78 @@ impl Scanner {
                     // file ended without closing block comment
                     if !(self.a_match('*') && self.a_match('/')) {
                         lox::error(self.line, "Unterminated block comment.");
-                        return;
                     }
+                    self.add_trivia(TokenType::Comment);
+                } else if self.a_match('=') {
+                    self.add_token(TokenType::SlashEqual);
                 } else {
                     self.add_token(TokenType::Slash);
                 }
             }
-            ' ' | '\r' => {} // do nothing for theses chars
+            ' ' | '\r' | '\t' => self.add_trivia(TokenType::Whitespace),
             '\n' => {
-                self.line += 1;
+                self.add_trivia(TokenType::Whitespace);
+                self.new_line();
             }
             '"' => self.string(),
             '0'..='9' => self.number(),
             'a'..='z' | 'A'..='Z' | '_' => self.identifier(),
-            x => lox::error(self.line, &format!("Unexpected character. '{}'", x)),
-        };
+            x => lox::error_at(
+                self.line,
+                self.column(),
+                &format!("Unexpected character. '{}'", x),
+            ),
+        }
     }
 
     fn string(&mut self) {
+        let mut value = String::new();
+        let mut has_invalid_escape = false;
+
         while self.peek() != '"' && !self.is_at_end() {
-            if self.peek() == '\n' {
-                self.line += 1
+            let c = self.advance();
+            match c {
+                '\\' if !self.is_at_end() => match self.advance() {
+                    'n' => value.push('\n'),
+                    't' => value.push('\t'),
+                    'r' => value.push('\r'),
+                    '0' => value.push('\0'),
+                    '\\' => value.push('\\'),
+                    '"' => value.push('"'),
+                    x => {
+                        lox::error_at(
+                            self.line,
+                            self.column(),
+                            &format!("Unknown escape sequence '\\{}'.", x),
+                        );
+                        has_invalid_escape = true;
+                    }
+                },
+                '\n' => {
+                    self.new_line();
+                    value.push(c);
+                }
+                c => value.push(c),
             }
-            self.advance();
         }
 
         // unterminated string
         if self.is_at_end() {
-            lox::error(self.line, "Unterminated string.");
+            lox::error_at(self.line, self.column(), "Unterminated string.");
             return;
         }
 
         // the closing "
         self.advance();
 
-        let value: String = self.source[self.start + 1..self.current - 1].into();
-
-        self.add_token(TokenType::String(value));
+        // the error is already reported, but a lossless scan still needs the text
+        if !has_invalid_escape || self.keep_trivia {
+            self.add_token(TokenType::String(value));
+        }
     }
 
     fn number(&mut self) {
-        while self.peek().is_ascii_digit() {
-            self.advance();
-        }
+        self.digits();
 
         let is_peek_next_digit = self
             .peek_next()
@@ -150,26 +229,77 @@ impl Scanner {
             .unwrap_or(false);
         if self.peek() == '.' && is_peek_next_digit {
             self.advance();
+            self.digits();
+        }
+
+        // exponent, as in `2.5e-3`
+        if matches!(self.peek(), 'e' | 'E') {
+            self.advance();
+            if matches!(self.peek(), '+' | '-') {
+                self.advance();
+            }
+            if !self.peek().is_ascii_digit() {
+                while self.peek().is_ascii_alphanumeric() || self.peek() == '_' {
+                    self.advance();
+                }
+                lox::error_at(self.line, self.column(), "Malformed exponent in number.");
+                return;
+            }
+            self.digits();
+        }
 
-            while self.peek().is_ascii_digit() {
+        // `123abc` is most likely a typo, so it's rejected instead of split in two tokens
+        if self.peek().is_ascii_alphabetic() || self.peek() == '_' {
+            while self.peek().is_ascii_alphanumeric() || self.peek() == '_' {
                 self.advance();
             }
+            lox::error_at(self.line, self.column(), "Invalid number literal.");
+            return;
+        }
+
+        // `_` separates digits, so it needs one on each side
+        let text: Vec<char> = self.text().chars().collect();
+        let is_digit_at = |index: Option<usize>| {
+            index
+                .and_then(|index| text.get(index))
+                .map(|c| c.is_ascii_digit())
+                .unwrap_or(false)
+        };
+        let misplaced_separator = text.iter().enumerate().any(|(index, c)| {
+            *c == '_' && !(is_digit_at(index.checked_sub(1)) && is_digit_at(Some(index + 1)))
+        });
+        if misplaced_separator {
+            lox::error_at(
+                self.line,
+                self.column(),
+                "Digit separator '_' must be between digits.",
+            );
+            return;
         }
 
         // Unwrap here is safe because digits are verified in if statements
-        let value: f64 = self.source[self.start..self.current].parse().unwrap();
+        let value: f64 = self.text().replace('_', "").parse().unwrap();
         self.add_token(TokenType::Number(value))
     }
 
+    // digits, possibly split by `_` as in `1_000`
+    fn digits(&mut self) {
+        while self.peek().is_ascii_digit() || self.peek() == '_' {
+            self.advance();
+        }
+    }
+
+    // `_` may appear anywhere in a name, not only first, so names like `is_truthy` are one token
     fn identifier(&mut self) {
-        while self.peek().is_ascii_alphanumeric() {
+        while self.peek().is_ascii_alphanumeric() || self.peek() == '_' {
             self.advance();
         }
 
-        let identifier = &self.source[self.start..self.current];
-        let kind = match identifier {
+        let kind = match self.text().as_str() {
             "and" => TokenType::And,
+            "break" => TokenType::Break,
             "class" => TokenType::Class,
+            "continue" => TokenType::Continue,
             "else" => TokenType::Else,
             "false" => TokenType::False,
             "for" => TokenType::For,
@@ -180,10 +310,12 @@ impl Scanner {
             "print" => TokenType::Print,
             "return" => TokenType::Return,
             "super" => TokenType::Super,
+            "switch" => TokenType::Switch,
             "this" => TokenType::This,
             "true" => TokenType::True,
             "var" => TokenType::Var,
             "while" => TokenType::While,
+            "xor" => TokenType::Xor,
             _ => TokenType::Identifier,
         };
 
@@ -195,7 +327,7 @@ impl Scanner {
         if self.is_at_end() {
             return false;
         };
-        if self.source.chars().nth(self.current) != Some(expected) {
+        if self.source[self.current] != expected {
             return false;
         };
 
@@ -207,12 +339,12 @@ impl Scanner {
         if self.is_at_end() {
             '\0'
         } else {
-            self.source.chars().nth(self.current).unwrap() //current will never pass the size of source
+            self.source[self.current]
         }
     }
 
     fn peek_next(&self) -> Option<char> {
-        self.source.chars().nth(self.current + 1)
+        self.source.get(self.current + 1).copied()
     }
 
     fn is_at_end(&self) -> bool {
@@ -221,13 +353,33 @@ impl Scanner {
 
     fn advance(&mut self) -> char {
         self.current += 1;
-        self.source.chars().nth(self.current - 1).unwrap() //current will never pass the size of source
+        self.source[self.current - 1] //current will never pass the size of source
+    }
+
+    // call right after consuming a '\n'
+    fn new_line(&mut self) {
+        self.line += 1;
+        self.line_start = self.current;
+    }
+
+    // column of the token being scanned, lines in multi-line tokens count from the token start
+    fn column(&self) -> usize {
+        self.start.saturating_sub(self.line_start) + 1
+    }
+
+    fn text(&self) -> String {
+        self.source[self.start..self.current].iter().collect()
+    }
+
+    fn add_trivia(&mut self, kind: TokenType) {
+        if self.keep_trivia {
+            self.add_token(kind);
+        }
     }
 
     fn add_token(&mut self, kind: TokenType) {
-        let text = &self.source[self.start..self.current];
-        self.tokens
-            .push(Token::new(kind, text.to_string(), self.line));
+        let token = Token::new(kind, self.text(), self.line).with_column(self.column());
+        self.tokens.push(token);
     }
 }
 
@@ -261,8 +413,7 @@ mod tests {
             TokenType::Star,
             TokenType::Plus,
             TokenType::Minus,
-            TokenType::Slash,
-            TokenType::Equal,
+            TokenType::SlashEqual,
             TokenType::Less,
             TokenType::Greater,
             TokenType::LessEqual,
@@ -347,6 +498,20 @@ mod tests {
         )
     }
 
+    #[test]
+    fn underscores_inside_identifiers() {
+        let mut scanner = Scanner::new("is_truthy a_ _b_c".into());
+        scanner.scan_tokens();
+
+        let lexemes: Vec<&str> = scanner
+            .tokens
+            .iter()
+            .filter(|token| token.kind == TokenType::Identifier)
+            .map(|token| token.lexeme.as_str())
+            .collect();
+        assert_eq!(lexemes, ["is_truthy", "a_", "_b_c"]);
+    }
+
     #[test]
     fn block_commentaries() {
         let source = r#"/* multi
@@ -382,4 +547,208 @@ mod tests {
 
         assert_eq!(token_types, vec![TokenType::Eof])
     }
+
+    #[test]
+    fn string_escape_sequences() {
+        let source = r#""a\nb" "say \"hi\"\t\\""#;
+
+        let mut scanner = Scanner::new(source.into());
+        scanner.scan_tokens();
+
+        let token_types: Vec<TokenType> = scanner
+            .tokens
+            .iter()
+            .map(|token| token.kind.clone())
+            .collect();
+
+        assert_eq!(
+            token_types,
+            vec![
+                TokenType::String("a\nb".into()),
+                TokenType::String("say \"hi\"\t\\".into()),
+                TokenType::Eof
+            ]
+        )
+    }
+
+    #[test]
+    fn string_invalid_escape_sequence() {
+        let source = r#""bad \q escape""#;
+
+        let mut scanner = Scanner::new(source.into());
+        scanner.scan_tokens();
+
+        let token_types: Vec<TokenType> = scanner
+            .tokens
+            .iter()
+            .map(|token| token.kind.clone())
+            .collect();
+
+        assert_eq!(token_types, vec![TokenType::Eof])
+    }
+
+    #[test]
+    fn token_columns() {
+        let source = "var answer = 42;\n  print answer;";
+
+        let mut scanner = Scanner::new(source.into());
+        scanner.scan_tokens();
+
+        let positions: Vec<(usize, usize)> = scanner
+            .tokens
+            .iter()
+            .map(|token| (token.line, token.column))
+            .collect();
+
+        assert_eq!(
+            positions,
+            vec![
+                (1, 1),
+                (1, 5),
+                (1, 12),
+                (1, 14),
+                (1, 16),
+                (2, 3),
+                (2, 9),
+                (2, 15),
+                (2, 16)
+            ]
+        );
+    }
+
+    #[test]
+    fn multi_byte_characters() {
+        let source = "\"héllo wörld 🦀\" + \"ü\"";
+
+        let mut scanner = Scanner::new(source.into());
+        scanner.scan_tokens();
+
+        let token_types: Vec<TokenType> = scanner
+            .tokens
+            .iter()
+            .map(|token| token.kind.clone())
+            .collect();
+
+        assert_eq!(
+            token_types,
+            vec![
+                TokenType::String("héllo wörld 🦀".into()),
+                TokenType::Plus,
+                TokenType::String("ü".into()),
+                TokenType::Eof
+            ]
+        );
+        assert_eq!(scanner.tokens[1].column, 17);
+    }
+
+    #[test]
+    fn large_input() {
+        let source = "var a = 1;\n".repeat(20000);
+
+        let mut scanner = Scanner::new(source);
+        scanner.scan_tokens();
+
+        assert_eq!(scanner.tokens.len(), 5 * 20000 + 1);
+        assert_eq!(scanner.tokens.last().unwrap().line, 20001);
+    }
+
+    #[test]
+    fn fragment_with_line_offset() {
+        let source = "var a = 1;\n\"two\nlines\" b;";
+
+        let mut scanner = Scanner::new(source.into()).with_start_line(40);
+        scanner.scan_tokens();
+
+        let lines: Vec<usize> = scanner.tokens.iter().map(|token| token.line).collect();
+
+        assert_eq!(lines, vec![40, 40, 40, 40, 40, 42, 42, 42, 42]);
+    }
+
+    #[test]
+    fn number_followed_by_identifier() {
+        let mut scanner = Scanner::new("123abc 4_".into());
+        scanner.scan_tokens();
+
+        let token_types: Vec<TokenType> = scanner.tokens.into_iter().map(|t| t.kind).collect();
+        assert_eq!(token_types, vec![TokenType::Eof]);
+    }
+
+    #[test]
+    fn scientific_notation() {
+        let mut scanner = Scanner::new("1e10 2.5e-3 3E+2".into());
+        scanner.scan_tokens();
+
+        let token_types: Vec<TokenType> = scanner.tokens.into_iter().map(|t| t.kind).collect();
+        assert_eq!(
+            token_types,
+            vec![
+                TokenType::Number(1e10),
+                TokenType::Number(2.5e-3),
+                TokenType::Number(300.0),
+                TokenType::Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_exponent() {
+        let mut scanner = Scanner::new("1e 2e+ 3ex".into());
+        scanner.scan_tokens();
+
+        let token_types: Vec<TokenType> = scanner.tokens.into_iter().map(|t| t.kind).collect();
+        assert_eq!(token_types, vec![TokenType::Eof]);
+    }
+
+    #[test]
+    fn digit_separators() {
+        let mut scanner = Scanner::new("1_000 1_000.5 2e1_0".into());
+        scanner.scan_tokens();
+
+        let token_types: Vec<TokenType> = scanner.tokens.into_iter().map(|t| t.kind).collect();
+        assert_eq!(
+            token_types,
+            vec![
+                TokenType::Number(1000.0),
+                TokenType::Number(1000.5),
+                TokenType::Number(2e10),
+                TokenType::Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn misplaced_digit_separators() {
+        for source in ["1_", "1__0", "1_.5"] {
+            let mut scanner = Scanner::new(source.into());
+            scanner.scan_tokens();
+
+            let token_types: Vec<TokenType> = scanner.tokens.into_iter().map(|t| t.kind).collect();
+            assert_eq!(token_types, vec![TokenType::Eof], "{}", source);
+        }
+    }
+
+    #[test]
+    fn leading_underscore_is_identifier() {
+        let mut scanner = Scanner::new("_1".into());
+        scanner.scan_tokens();
+
+        assert_eq!(scanner.tokens[0].kind, TokenType::Identifier);
+    }
+
+    #[test]
+    fn method_access_on_number() {
+        let mut scanner = Scanner::new("3.foo".into());
+        scanner.scan_tokens();
+
+        let token_types: Vec<TokenType> = scanner.tokens.into_iter().map(|t| t.kind).collect();
+        assert_eq!(
+            token_types,
+            vec![
+                TokenType::Number(3.0),
+                TokenType::Dot,
+                TokenType::Identifier,
+                TokenType::Eof
+            ]
+        );
+    }
 }
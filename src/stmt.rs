@@ -1,20 +1,41 @@
 use crate::expr::Expr;
 use crate::token::Token;
 
-pub type Function = (Token, Vec<Token>, Vec<Stmt>);
+pub type Function = (Token, Vec<Token>, Vec<Stmt>, Signature);
+pub type Case = (Expr, Vec<Stmt>); // `case value:` and the statements run when it matches
+
+// Optional type annotations of a function, as in `fun f(a: Number) -> String`.
+// They're parsed and kept for tools but not enforced when running.
+#[derive(Debug, Clone, Default)]
+pub struct Signature {
+    pub params: Vec<Option<Token>>, // one per parameter
+    pub returns: Option<Box<Token>>,
+    pub doc: Option<String>, // `///` lines right above the function
+}
 
 #[derive(Debug, Clone)]
 pub enum Stmt {
     Block(Vec<Stmt>),
     Expression(Expr),
-    Print(Expr),
-    Var(Token, Option<Expr>),
+    Print(Vec<Expr>), // `print a, b;` prints every value on one line
+    Var(Token, Option<Box<Token>>, Option<Expr>), // name, `: Type` annotation and initializer
     If(Expr, Box<Stmt>, Option<Box<Stmt>>),
-    Function(Token, Vec<Token>, Vec<Stmt>),
-    While(Expr, Box<Stmt>),
-    Return(Token, Expr),
+    Function(Token, Vec<Token>, Vec<Stmt>, Signature),
+    While {
+        condition: Expr,
+        body: Box<Stmt>,
+        increment: Option<Box<Expr>>, // kept apart from the body so `continue` still runs it
+        else_branch: Option<Box<Stmt>>,
+    },
+    // scrutinee, cases and `default:` statements. Cases don't fall through, so a `break` in one
+    // belongs to the loop around the switch.
+    Switch(Expr, Vec<Case>, Option<Vec<Stmt>>),
+    Return(Token, Option<Expr>), // no value for a bare `return;`
+    Break(Token),
+    Continue(Token),
     Class {
         token: Token,
+        superclass: Option<Expr>,
         methods: Vec<Function>,
     },
 }
@@ -24,17 +45,38 @@ impl Stmt {
         match self {
             Stmt::Block(statements) => visitor.visit_block_stmt(statements),
             Stmt::Expression(expr) => visitor.visit_expression_stmt(expr),
-            Stmt::Print(expr) => visitor.visit_print_stmt(expr),
-            Stmt::Var(token, expr) => visitor.visit_var_stmt(token, expr.as_ref()),
+            Stmt::Print(values) => visitor.visit_print_stmt(values),
+            Stmt::Var(token, annotation, expr) => {
+                visitor.visit_var_stmt(token, annotation.as_deref(), expr.as_ref())
+            }
             Stmt::If(cond, then_branch, else_branch) => {
                 visitor.visit_if_stmt(cond, then_branch, else_branch.as_deref())
             }
-            Stmt::While(cond, block) => visitor.visit_while_stmt(cond, block),
-            Stmt::Function(token, parameters, body) => {
-                visitor.visit_function_stmt(token, parameters, body)
+            Stmt::While {
+                condition,
+                body,
+                increment,
+                else_branch,
+            } => visitor.visit_while_stmt(
+                condition,
+                body,
+                increment.as_deref(),
+                else_branch.as_deref(),
+            ),
+            Stmt::Function(token, parameters, body, signature) => {
+                visitor.visit_function_stmt(token, parameters, body, signature)
+            }
+            Stmt::Switch(value, cases, default) => {
+                visitor.visit_switch_stmt(value, cases, default.as_deref())
             }
-            Stmt::Return(token, expr) => visitor.visit_return_stmt(token, expr),
-            Stmt::Class { token, methods } => visitor.visit_class_stmt(token, methods),
+            Stmt::Return(token, expr) => visitor.visit_return_stmt(token, expr.as_ref()),
+            Stmt::Break(token) => visitor.visit_break_stmt(token),
+            Stmt::Continue(token) => visitor.visit_continue_stmt(token),
+            Stmt::Class {
+                token,
+                superclass,
+                methods,
+            } => visitor.visit_class_stmt(token, superclass.as_ref(), methods),
         }
     }
 }
@@ -42,11 +84,36 @@ impl Stmt {
 pub trait Visitor<T> {
     fn visit_block_stmt(&mut self, statements: &[Stmt]) -> T;
     fn visit_expression_stmt(&mut self, expr: &Expr) -> T;
-    fn visit_print_stmt(&mut self, expr: &Expr) -> T;
-    fn visit_var_stmt(&mut self, token: &Token, expr: Option<&Expr>) -> T;
+    fn visit_print_stmt(&mut self, values: &[Expr]) -> T;
+    fn visit_var_stmt(
+        &mut self,
+        token: &Token,
+        annotation: Option<&Token>,
+        expr: Option<&Expr>,
+    ) -> T;
     fn visit_if_stmt(&mut self, cond: &Expr, then_branch: &Stmt, else_branch: Option<&Stmt>) -> T;
-    fn visit_while_stmt(&mut self, cond: &Expr, block: &Stmt) -> T;
-    fn visit_function_stmt(&mut self, name: &Token, params: &[Token], body: &[Stmt]) -> T;
-    fn visit_return_stmt(&mut self, token: &Token, expr: &Expr) -> T;
-    fn visit_class_stmt(&mut self, token: &Token, methods: &[Function]) -> T;
+    fn visit_while_stmt(
+        &mut self,
+        cond: &Expr,
+        block: &Stmt,
+        increment: Option<&Expr>,
+        else_branch: Option<&Stmt>,
+    ) -> T;
+    fn visit_function_stmt(
+        &mut self,
+        name: &Token,
+        params: &[Token],
+        body: &[Stmt],
+        signature: &Signature,
+    ) -> T;
+    fn visit_switch_stmt(&mut self, value: &Expr, cases: &[Case], default: Option<&[Stmt]>) -> T;
+    fn visit_return_stmt(&mut self, token: &Token, expr: Option<&Expr>) -> T;
+    fn visit_break_stmt(&mut self, token: &Token) -> T;
+    fn visit_continue_stmt(&mut self, token: &Token) -> T;
+    fn visit_class_stmt(
+        &mut self,
+        token: &Token,
+        superclass: Option<&Expr>,
+        methods: &[Function],
+    ) -> T;
 }
@@ -6,11 +6,34 @@ pub struct Token {
     pub kind: TokenType,
     pub lexeme: String,
     pub line: usize,
+    pub column: usize, // 1-based, 0 when the token wasn't read from source
 }
 
 impl Token {
     pub fn new(kind: TokenType, lexeme: String, line: usize) -> Token {
-        Token { kind, lexeme, line }
+        Token {
+            kind,
+            lexeme,
+            line,
+            column: 0,
+        }
+    }
+
+    pub fn with_column(mut self, column: usize) -> Token {
+        self.column = column;
+        self
+    }
+
+    // Where the token starts, as shown in error messages
+    pub fn position(&self) -> String {
+        position(self.line, self.column)
+    }
+}
+
+pub fn position(line: usize, column: usize) -> String {
+    match column {
+        0 => format!("line {}", line),
+        column => format!("line {} col {}", line, column),
     }
 }
 
@@ -42,6 +65,13 @@ mod test {
         assert_eq!(token.to_string(), "Comma , ");
     }
 
+    #[test]
+    fn position_with_column() {
+        let token = Token::new(TokenType::Comma, ",".into(), 10).with_column(4);
+
+        assert_eq!(token.position(), "line 10 col 4");
+    }
+
     #[test]
     fn correct_display_for_literal_token() {
         let token = Token::new(TokenType::String("Example text".into()), "\"".into(), 10);
@@ -0,0 +1,195 @@
+use super::expr::Expr;
+use super::stmt::Stmt;
+
+// Rewrites every expression in `stmts` with `f`. Children are rewritten before their parent,
+// so `f` always sees subexpressions that were already transformed.
+pub fn transform_stmts(stmts: Vec<Stmt>, f: &mut impl FnMut(Expr) -> Expr) -> Vec<Stmt> {
+    stmts
+        .into_iter()
+        .map(|stmt| transform_stmt(stmt, f))
+        .collect()
+}
+
+pub fn transform_stmt(stmt: Stmt, f: &mut impl FnMut(Expr) -> Expr) -> Stmt {
+    match stmt {
+        Stmt::Block(statements) => Stmt::Block(transform_stmts(statements, f)),
+        Stmt::Expression(expr) => Stmt::Expression(transform_expr(expr, f)),
+        Stmt::Print(values) => Stmt::Print(
+            values
+                .into_iter()
+                .map(|value| transform_expr(value, f))
+                .collect(),
+        ),
+        Stmt::Var(token, annotation, initializer) => Stmt::Var(
+            token,
+            annotation,
+            initializer.map(|expr| transform_expr(expr, f)),
+        ),
+        Stmt::If(cond, then_branch, else_branch) => Stmt::If(
+            transform_expr(cond, f),
+            Box::new(transform_stmt(*then_branch, f)),
+            else_branch.map(|stmt| Box::new(transform_stmt(*stmt, f))),
+        ),
+        Stmt::Function(name, params, body, signature) => {
+            Stmt::Function(name, params, transform_stmts(body, f), signature)
+        }
+        Stmt::While {
+            condition,
+            body,
+            increment,
+            else_branch,
+        } => Stmt::While {
+            condition: transform_expr(condition, f),
+            body: Box::new(transform_stmt(*body, f)),
+            increment: increment.map(|expr| Box::new(transform_expr(*expr, f))),
+            else_branch: else_branch.map(|stmt| Box::new(transform_stmt(*stmt, f))),
+        },
+        Stmt::Switch(value, cases, default) => Stmt::Switch(
+            transform_expr(value, f),
+            cases
+                .into_iter()
+                .map(|(case, body)| (transform_expr(case, f), transform_stmts(body, f)))
+                .collect(),
+            default.map(|body| transform_stmts(body, f)),
+        ),
+        Stmt::Return(token, expr) => Stmt::Return(token, expr.map(|expr| transform_expr(expr, f))),
+        Stmt::Break(token) => Stmt::Break(token),
+        Stmt::Continue(token) => Stmt::Continue(token),
+        // the superclass is a name reference rather than a computed value, so it's left as is
+        Stmt::Class {
+            token,
+            superclass,
+            methods,
+        } => Stmt::Class {
+            token,
+            superclass,
+            methods: methods
+                .into_iter()
+                .map(|(name, params, body, signature)| {
+                    (name, params, transform_stmts(body, f), signature)
+                })
+                .collect(),
+        },
+    }
+}
+
+pub fn transform_expr(expr: Expr, f: &mut impl FnMut(Expr) -> Expr) -> Expr {
+    let boxed = |expr: Box<Expr>, f: &mut _| Box::new(transform_expr(*expr, f));
+
+    let expr = match expr {
+        Expr::Binary(left, token, right) => {
+            let left = boxed(left, f);
+            Expr::Binary(left, token, boxed(right, f))
+        }
+        Expr::Grouping(expr) => Expr::Grouping(boxed(expr, f)),
+        Expr::Unary(token, expr) => Expr::Unary(token, boxed(expr, f)),
+        Expr::Conditional(cond, then_branch, else_branch) => {
+            let cond = boxed(cond, f);
+            let then_branch = boxed(then_branch, f);
+            Expr::Conditional(cond, then_branch, boxed(else_branch, f))
+        }
+        Expr::Call(callee, token, arguments) => {
+            let callee = boxed(callee, f);
+            let arguments = arguments
+                .into_iter()
+                .map(|argument| transform_expr(argument, f))
+                .collect();
+            Expr::Call(callee, token, arguments)
+        }
+        Expr::Get(object, name) => Expr::Get(boxed(object, f), name),
+        Expr::Set(object, name, value) => {
+            let object = boxed(object, f);
+            Expr::Set(object, name, boxed(value, f))
+        }
+        Expr::CompoundSet(object, name, operator, value) => {
+            let object = boxed(object, f);
+            Expr::CompoundSet(object, name, operator, boxed(value, f))
+        }
+        Expr::Lambda(params, body) => Expr::Lambda(params, transform_stmts(body, f)),
+        Expr::Map(brace, entries) => {
+            let entries = entries
+                .into_iter()
+                .map(|(key, value)| {
+                    let key = transform_expr(key, f);
+                    (key, transform_expr(value, f))
+                })
+                .collect();
+            Expr::Map(brace, entries)
+        }
+        Expr::Index(object, bracket, index) => {
+            let object = boxed(object, f);
+            Expr::Index(object, bracket, boxed(index, f))
+        }
+        Expr::IndexSet(object, bracket, index, value) => {
+            let object = boxed(object, f);
+            let index = boxed(index, f);
+            Expr::IndexSet(object, bracket, index, boxed(value, f))
+        }
+        Expr::Assign(token, value, id) => Expr::Assign(token, boxed(value, f), id),
+        Expr::LogicOr(left, right) => {
+            let left = boxed(left, f);
+            Expr::LogicOr(left, boxed(right, f))
+        }
+        Expr::LogicXor(left, right) => {
+            let left = boxed(left, f);
+            Expr::LogicXor(left, boxed(right, f))
+        }
+        Expr::LogicAnd(left, right) => {
+            let left = boxed(left, f);
+            Expr::LogicAnd(left, boxed(right, f))
+        }
+        leaf @ (Expr::This(..)
+        | Expr::Super(..)
+        | Expr::Variable(..)
+        | Expr::Number(_)
+        | Expr::String(_)
+        | Expr::Boolean(_)
+        | Expr::Nil) => leaf,
+    };
+
+    f(expr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast_printer::ASTPrinter;
+    use crate::interpreter::Interpreter;
+    use crate::lox;
+    use crate::object::Object;
+    use crate::resolver::Resolver;
+    use crate::token::Token;
+    use crate::token_type::TokenType;
+
+    fn increment_numbers(expr: Expr) -> Expr {
+        match expr {
+            Expr::Number(n) => Expr::Number(n + 1.0),
+            expr => expr,
+        }
+    }
+
+    #[test]
+    fn rewrite_numbers() {
+        let stmts = lox::run("var a = 1 + 2 * 3; print a;".to_string());
+        let stmts = transform_stmts(stmts, &mut increment_numbers);
+
+        assert_eq!(ASTPrinter::print_stmt(&stmts[0]), "(var a = (+ 2 (* 3 4)))");
+
+        let depth_map = Resolver::new().run(&stmts).unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.add_expr_ids_depth(depth_map);
+        interpreter.interpret(&stmts);
+
+        let token = Token::new(TokenType::Identifier, "a".to_string(), 0);
+        let value = interpreter.environment().borrow().get(&token).unwrap();
+        assert_eq!(value, Object::Number(14.0));
+    }
+
+    #[test]
+    fn rewrite_inside_function_bodies() {
+        let stmts = lox::run("fun f() { return 1; }".to_string());
+        let stmts = transform_stmts(stmts, &mut increment_numbers);
+
+        assert_eq!(ASTPrinter::print_stmt(&stmts[0]), "(fun f() (return 2))");
+    }
+}
@@ -0,0 +1,573 @@
+use super::expr;
+use super::expr::Expr;
+use super::lox;
+use super::stmt;
+use super::stmt::{Case, Function, Signature, Stmt};
+use super::token::Token;
+use crate::error::{LoxError, Result};
+use crate::token_type::TokenType;
+use std::collections::HashMap;
+
+// Type of an expression when it can be told without running it, like "Number" for `1 + 2`
+type Inferred = Option<String>;
+
+#[derive(Clone, Default)]
+struct Binding {
+    annotation: Option<String>, // declared type of a variable
+    returns: Option<String>,    // declared return type of a function
+}
+
+// Best-effort check of the optional type annotations. Only mismatches between an annotation
+// and a value whose type is known for sure are reported, so unannotated code always passes.
+pub struct TypeChecker {
+    scopes: Vec<HashMap<String, Binding>>,
+    return_types: Vec<Option<String>>, // one per function being checked, innermost last
+    strict: bool,                      // mismatches are errors instead of warnings
+    warnings: Vec<String>,
+}
+
+impl Default for TypeChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TypeChecker {
+    pub fn new() -> Self {
+        TypeChecker {
+            scopes: vec![HashMap::new()],
+            return_types: vec![],
+            strict: false,
+            warnings: vec![],
+        }
+    }
+
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    pub fn check(&mut self, statements: &[Stmt]) -> Result<()> {
+        statements.iter().try_for_each(|stmt| stmt.accept(self))
+    }
+
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    fn mismatch(&mut self, token: &Token, message: String) -> Result<()> {
+        if self.strict {
+            return Err(LoxError::TypeError(token.clone(), message));
+        }
+
+        self.warnings.push(lox::warning(token, &message));
+        Ok(())
+    }
+
+    fn infer(&mut self, expr: &Expr) -> Result<Inferred> {
+        expr.accept(self)
+    }
+
+    fn declare(&mut self, name: &Token, binding: Binding) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.lexeme.clone(), binding);
+        }
+    }
+
+    fn lookup(&self, name: &Token) -> Binding {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&name.lexeme))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn function(
+        &mut self,
+        params: &[Token],
+        body: &[Stmt],
+        param_types: &[Option<Token>],
+        returns: Option<&Token>,
+    ) -> Result<()> {
+        self.scopes.push(HashMap::new());
+        self.return_types.push(annotation_name(returns));
+        for (index, param) in params.iter().enumerate() {
+            let annotation = param_types
+                .get(index)
+                .cloned()
+                .flatten()
+                .map(|token| type_name(&token.lexeme));
+            self.declare(
+                param,
+                Binding {
+                    annotation,
+                    returns: None,
+                },
+            );
+        }
+
+        let result = self.check(body);
+        self.return_types.pop();
+        self.scopes.pop();
+        result
+    }
+}
+
+fn annotation_name(annotation: Option<&Token>) -> Option<String> {
+    annotation.map(|token| type_name(&token.lexeme))
+}
+
+// Built-in type names can be written in any case, so `number` and `Number` are the same type.
+// Other names are class names and are kept as written.
+fn type_name(name: &str) -> String {
+    match name.to_lowercase().as_str() {
+        "number" => "Number".to_string(),
+        "string" => "String".to_string(),
+        "boolean" => "Boolean".to_string(),
+        "nil" => "Nil".to_string(),
+        _ => name.to_string(),
+    }
+}
+
+fn known(name: &str) -> Inferred {
+    Some(name.to_string())
+}
+
+impl expr::Visitor<Result<Inferred>> for TypeChecker {
+    fn visit_binary_expr(&mut self, left: &Expr, token: &Token, right: &Expr) -> Result<Inferred> {
+        let left = self.infer(left)?;
+        let right = self.infer(right)?;
+        let is = |side: &Inferred, name: &str| side.as_deref() == Some(name);
+
+        Ok(match token.kind {
+            TokenType::Plus if is(&left, "String") || is(&right, "String") => known("String"),
+            TokenType::Plus if is(&left, "Number") && is(&right, "Number") => known("Number"),
+            TokenType::Plus => None,
+            TokenType::Star if is(&left, "String") => known("String"),
+            TokenType::Minus | TokenType::Star | TokenType::Slash => known("Number"),
+            TokenType::Greater
+            | TokenType::GreaterEqual
+            | TokenType::Less
+            | TokenType::LessEqual
+            | TokenType::EqualEqual
+            | TokenType::BangEqual => known("Boolean"),
+            _ => None,
+        })
+    }
+
+    fn visit_grouping_expr(&mut self, expr: &Expr) -> Result<Inferred> {
+        self.infer(expr)
+    }
+
+    fn visit_unary_expr(&mut self, token: &Token, expr: &Expr) -> Result<Inferred> {
+        self.infer(expr)?;
+        Ok(match token.kind {
+            TokenType::Minus => known("Number"),
+            TokenType::Bang => known("Boolean"),
+            _ => None,
+        })
+    }
+
+    fn visit_call_expr(
+        &mut self,
+        callee: &Expr,
+        _token: &Token,
+        args: &[Expr],
+    ) -> Result<Inferred> {
+        self.infer(callee)?;
+        for arg in args {
+            self.infer(arg)?;
+        }
+
+        match callee {
+            Expr::Variable(name, _) => Ok(self.lookup(name).returns),
+            _ => Ok(None),
+        }
+    }
+
+    fn visit_conditional_expr(
+        &mut self,
+        cond: &Expr,
+        then_branch: &Expr,
+        else_branch: &Expr,
+    ) -> Result<Inferred> {
+        self.infer(cond)?;
+        let then_type = self.infer(then_branch)?;
+        let else_type = self.infer(else_branch)?;
+        Ok(then_type.filter(|then_type| Some(then_type) == else_type.as_ref()))
+    }
+
+    fn visit_literal_expr_number(&mut self, _value: f64) -> Result<Inferred> {
+        Ok(known("Number"))
+    }
+
+    fn visit_literal_expr_string(&mut self, _value: &str) -> Result<Inferred> {
+        Ok(known("String"))
+    }
+
+    fn visit_literal_expr_boolean(&mut self, _value: bool) -> Result<Inferred> {
+        Ok(known("Boolean"))
+    }
+
+    fn visit_literal_expr_nil(&mut self) -> Result<Inferred> {
+        Ok(known("Nil"))
+    }
+
+    fn visit_variable_expr(&mut self, token: &Token, _id: u64) -> Result<Inferred> {
+        Ok(self.lookup(token).annotation)
+    }
+
+    fn visit_assign_expr(&mut self, token: &Token, expr: &Expr, _id: u64) -> Result<Inferred> {
+        let value = self.infer(expr)?;
+        if let (Some(declared), Some(value)) = (self.lookup(token).annotation, &value) {
+            if &declared != value {
+                self.mismatch(
+                    token,
+                    format!(
+                        "Variable '{}' is declared as {} but assigned a {}",
+                        token.lexeme, declared, value
+                    ),
+                )?;
+            }
+        }
+        Ok(value)
+    }
+
+    fn visit_logic_or(&mut self, left: &Expr, right: &Expr) -> Result<Inferred> {
+        self.infer(left)?;
+        self.infer(right)?;
+        Ok(None)
+    }
+
+    fn visit_logic_xor(&mut self, left: &Expr, right: &Expr) -> Result<Inferred> {
+        self.infer(left)?;
+        self.infer(right)?;
+        Ok(known("Boolean"))
+    }
+
+    fn visit_logic_and(&mut self, left: &Expr, right: &Expr) -> Result<Inferred> {
+        self.infer(left)?;
+        self.infer(right)?;
+        Ok(None)
+    }
+
+    fn visit_get_expr(&mut self, object: &Expr, _property: &Token) -> Result<Inferred> {
+        self.infer(object)?;
+        Ok(None)
+    }
+
+    fn visit_set_expr(
+        &mut self,
+        object: &Expr,
+        _property: &Token,
+        value: &Expr,
+    ) -> Result<Inferred> {
+        self.infer(object)?;
+        self.infer(value)
+    }
+
+    fn visit_compound_set_expr(
+        &mut self,
+        object: &Expr,
+        _property: &Token,
+        _operator: &Token,
+        value: &Expr,
+    ) -> Result<Inferred> {
+        self.infer(object)?;
+        self.infer(value)?;
+        Ok(None)
+    }
+
+    fn visit_this_expr(&mut self, _token: &Token, _id: u64) -> Result<Inferred> {
+        Ok(None)
+    }
+
+    fn visit_super_expr(
+        &mut self,
+        _keyword: &Token,
+        _method: &Token,
+        _id: u64,
+    ) -> Result<Inferred> {
+        Ok(None)
+    }
+
+    fn visit_lambda_expr(&mut self, params: &[Token], body: &[Stmt]) -> Result<Inferred> {
+        self.function(params, body, &[], None)?;
+        Ok(None)
+    }
+
+    fn visit_map_expr(&mut self, _brace: &Token, entries: &[(Expr, Expr)]) -> Result<Inferred> {
+        for (key, value) in entries {
+            self.infer(key)?;
+            self.infer(value)?;
+        }
+        Ok(None)
+    }
+
+    fn visit_index_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        index: &Expr,
+    ) -> Result<Inferred> {
+        self.infer(object)?;
+        self.infer(index)?;
+        Ok(None)
+    }
+
+    fn visit_index_set_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> Result<Inferred> {
+        self.infer(object)?;
+        self.infer(index)?;
+        self.infer(value)
+    }
+}
+
+impl stmt::Visitor<Result<()>> for TypeChecker {
+    fn visit_block_stmt(&mut self, statements: &[Stmt]) -> Result<()> {
+        self.scopes.push(HashMap::new());
+        let result = self.check(statements);
+        self.scopes.pop();
+        result
+    }
+
+    fn visit_expression_stmt(&mut self, expr: &Expr) -> Result<()> {
+        self.infer(expr).map(|_| ())
+    }
+
+    fn visit_print_stmt(&mut self, values: &[Expr]) -> Result<()> {
+        values
+            .iter()
+            .try_for_each(|value| self.infer(value).map(|_| ()))
+    }
+
+    fn visit_var_stmt(
+        &mut self,
+        token: &Token,
+        annotation: Option<&Token>,
+        expr: Option<&Expr>,
+    ) -> Result<()> {
+        let value = match expr {
+            Some(expr) => self.infer(expr)?,
+            None => None,
+        };
+        let annotation = annotation_name(annotation);
+        if let (Some(declared), Some(value)) = (&annotation, &value) {
+            if declared != value {
+                self.mismatch(
+                    token,
+                    format!(
+                        "Variable '{}' is declared as {} but initialized with a {}",
+                        token.lexeme, declared, value
+                    ),
+                )?;
+            }
+        }
+
+        self.declare(
+            token,
+            Binding {
+                annotation,
+                returns: None,
+            },
+        );
+        Ok(())
+    }
+
+    fn visit_if_stmt(
+        &mut self,
+        cond: &Expr,
+        then_branch: &Stmt,
+        else_branch: Option<&Stmt>,
+    ) -> Result<()> {
+        self.infer(cond)?;
+        then_branch.accept(self)?;
+        if let Some(else_branch) = else_branch {
+            else_branch.accept(self)?;
+        }
+        Ok(())
+    }
+
+    fn visit_while_stmt(
+        &mut self,
+        cond: &Expr,
+        block: &Stmt,
+        increment: Option<&Expr>,
+        else_branch: Option<&Stmt>,
+    ) -> Result<()> {
+        self.infer(cond)?;
+        block.accept(self)?;
+        if let Some(increment) = increment {
+            self.infer(increment)?;
+        }
+        if let Some(else_branch) = else_branch {
+            else_branch.accept(self)?;
+        }
+        Ok(())
+    }
+
+    fn visit_function_stmt(
+        &mut self,
+        name: &Token,
+        params: &[Token],
+        body: &[Stmt],
+        signature: &Signature,
+    ) -> Result<()> {
+        // declared first so recursive calls know the return type
+        self.declare(
+            name,
+            Binding {
+                annotation: None,
+                returns: annotation_name(signature.returns.as_deref()),
+            },
+        );
+        self.function(
+            params,
+            body,
+            &signature.params,
+            signature.returns.as_deref(),
+        )
+    }
+
+    fn visit_switch_stmt(
+        &mut self,
+        value: &Expr,
+        cases: &[Case],
+        default: Option<&[Stmt]>,
+    ) -> Result<()> {
+        self.infer(value)?;
+        for (case, body) in cases {
+            self.infer(case)?;
+            self.visit_block_stmt(body)?;
+        }
+        match default {
+            Some(default) => self.visit_block_stmt(default),
+            None => Ok(()),
+        }
+    }
+
+    fn visit_return_stmt(&mut self, token: &Token, expr: Option<&Expr>) -> Result<()> {
+        let value = match expr {
+            Some(expr) => self.infer(expr)?,
+            None => known("Nil"),
+        };
+        let declared = self.return_types.last().cloned().flatten();
+        if let (Some(declared), Some(value)) = (declared, value) {
+            if declared != value {
+                self.mismatch(
+                    token,
+                    format!(
+                        "Function is declared to return {} but returns a {}",
+                        declared, value
+                    ),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_break_stmt(&mut self, _token: &Token) -> Result<()> {
+        Ok(())
+    }
+
+    fn visit_continue_stmt(&mut self, _token: &Token) -> Result<()> {
+        Ok(())
+    }
+
+    fn visit_class_stmt(
+        &mut self,
+        token: &Token,
+        superclass: Option<&Expr>,
+        methods: &[Function],
+    ) -> Result<()> {
+        self.declare(token, Binding::default());
+        if let Some(superclass) = superclass {
+            self.infer(superclass)?;
+        }
+        methods.iter().try_for_each(|(_, params, body, signature)| {
+            self.function(
+                params,
+                body,
+                &signature.params,
+                signature.returns.as_deref(),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lox;
+
+    fn check(source: &str) -> TypeChecker {
+        let stmts = lox::run(source.to_string());
+        let mut checker = TypeChecker::new();
+        checker.check(&stmts).unwrap();
+        checker
+    }
+
+    #[test]
+    fn return_type_mismatch() {
+        let checker = check("fun f() -> Number {\n return \"one\";\n}");
+
+        assert_eq!(
+            checker.warnings(),
+            ["[line 2 col 2] Warning: Function is declared to return Number but returns a String"]
+        );
+    }
+
+    #[test]
+    fn assignment_type_mismatch() {
+        let checker = check("var x: Number = 1;\nx = \"a\" + x;");
+
+        assert_eq!(
+            checker.warnings(),
+            ["[line 2 col 1] Warning: Variable 'x' is declared as Number but assigned a String"]
+        );
+    }
+
+    #[test]
+    fn builtin_type_names_ignore_case() {
+        let checker =
+            check("var x: number = 1;\nfun f(s: string) -> boolean { return s == \"a\"; }");
+        assert!(checker.warnings().is_empty());
+
+        let checker = check("var x: number = \"a\";");
+        assert_eq!(
+            checker.warnings(),
+            ["[line 1 col 5] Warning: Variable 'x' is declared as Number but initialized with a String"]
+        );
+    }
+
+    #[test]
+    fn well_typed_program_passes() {
+        let checker = check(
+            r#"
+            fun area(width: Number, height: Number) -> Number {
+                return width * height;
+            }
+            var a: Number = area(2, 3);
+            var label: String = "area " + a;
+            var unknown = nil;
+            a = unknown;
+            "#,
+        );
+
+        assert!(checker.warnings().is_empty());
+    }
+
+    #[test]
+    fn strict_mismatch_is_error() {
+        let stmts = lox::run("var x: String = 1 + 2;".to_string());
+        let result = TypeChecker::new().with_strict(true).check(&stmts);
+
+        assert!(matches!(result, Err(LoxError::TypeError(token, _)) if token.lexeme == "x"));
+    }
+}
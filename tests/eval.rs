@@ -0,0 +1,12 @@
+use crafting_interpreters::interpreter::Interpreter;
+use crafting_interpreters::Object;
+
+#[test]
+fn eval_returns_the_last_value() {
+    let mut interpreter = Interpreter::new();
+    assert_eq!(interpreter.eval("1 + 2"), Ok(Object::Number(3.0)));
+    assert_eq!(
+        interpreter.eval("var a = \"b\"; a + \"c\""),
+        Ok(Object::String("bc".to_string()))
+    );
+}
@@ -0,0 +1,75 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn run_script(name: &str, source: &str) -> Option<i32> {
+    run_script_with_flags(&[], name, source)
+}
+
+fn run_script_with_flags(flags: &[&str], name: &str, source: &str) -> Option<i32> {
+    let path: PathBuf = std::env::temp_dir().join(name);
+    fs::write(&path, source).unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_crafting_interpreters"))
+        .args(flags)
+        .arg(&path)
+        .status()
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+
+    status.code()
+}
+
+#[test]
+fn successful_script_exits_with_zero() {
+    assert_eq!(run_script("exit_code_ok.lox", "print 1 + 2;"), Some(0));
+}
+
+#[test]
+fn parse_error_exits_with_65() {
+    assert_eq!(run_script("exit_code_parse.lox", "print 1 +;"), Some(65));
+}
+
+#[test]
+fn runtime_error_exits_with_70() {
+    assert_eq!(
+        run_script("exit_code_runtime.lox", "print -\"a\";"),
+        Some(70)
+    );
+}
+
+#[test]
+fn token_dump_of_missing_file_exits_with_66() {
+    let status = Command::new(env!("CARGO_BIN_EXE_crafting_interpreters"))
+        .args(["--tokens", "/nonexistent.lox"])
+        .status()
+        .unwrap();
+
+    assert_eq!(status.code(), Some(66));
+}
+
+#[test]
+fn ast_of_invalid_script_exits_with_65() {
+    assert_eq!(
+        run_script_with_flags(&["--ast"], "exit_code_ast_parse.lox", "print 1 +;"),
+        Some(65)
+    );
+}
+
+#[test]
+fn ast_stats_of_invalid_script_exits_with_65() {
+    assert_eq!(
+        run_script_with_flags(&["--ast-stats"], "exit_code_stats_parse.lox", "print 1 +;"),
+        Some(65)
+    );
+}
+
+#[test]
+fn ast_of_missing_file_exits_with_66() {
+    let status = Command::new(env!("CARGO_BIN_EXE_crafting_interpreters"))
+        .args(["--ast", "/nonexistent.lox"])
+        .status()
+        .unwrap();
+
+    assert_eq!(status.code(), Some(66));
+}
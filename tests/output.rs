@@ -0,0 +1,94 @@
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+fn run_script(name: &str, source: &str) -> String {
+    run_script_with_flags(&[], name, source)
+}
+
+fn run_script_with_flags(flags: &[&str], name: &str, source: &str) -> String {
+    let path: PathBuf = std::env::temp_dir().join(name);
+    fs::write(&path, source).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_crafting_interpreters"))
+        .args(flags)
+        .arg(&path)
+        .output()
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn write_has_no_trailing_newline() {
+    assert_eq!(
+        run_script(
+            "output_write.lox",
+            "write(\"a\"); write(\"b\"); print \"c\";"
+        ),
+        "abc\n"
+    );
+}
+
+#[test]
+fn print_separates_values_with_spaces() {
+    assert_eq!(
+        run_script("output_print.lox", "print 1, \"two\", nil; print 3;"),
+        "1 two nil\n3\n"
+    );
+}
+
+#[test]
+fn script_from_stdin() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_crafting_interpreters"))
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"var a = 1;\nprint a + 2;\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "3\n");
+}
+
+#[test]
+fn emit_tokens_prints_one_token_per_line() {
+    let output = run_script_with_flags(
+        &["--emit-tokens"],
+        "output_tokens.lox",
+        "var a = 1;\nprint a;",
+    );
+
+    assert_eq!(
+        output,
+        "[line 1] Var var \n\
+         [line 1] Identifier a \n\
+         [line 1] Equal = \n\
+         [line 1] Number 1 1\n\
+         [line 1] Semicolon ; \n\
+         [line 2] Print print \n\
+         [line 2] Identifier a \n\
+         [line 2] Semicolon ; \n\
+         [line 2] Eof  \n"
+    );
+}
+
+#[test]
+fn function_assigns_global_declared_later() {
+    assert_eq!(
+        run_script(
+            "output_late_global.lox",
+            "fun setX() { x = 5; } fun getX() { return x; } var x = 1; setX(); print getX();"
+        ),
+        "5\n"
+    );
+}